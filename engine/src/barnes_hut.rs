@@ -0,0 +1,292 @@
+use crate::{Particle, Vec2};
+
+/// Marker for a missing child or an empty leaf
+const NONE: u32 = u32::MAX;
+
+/// Marker for a leaf at maximum depth that holds several coincident bodies
+const MULTIPLE: u32 = u32::MAX - 1;
+
+/// Depth limit, so particles at (nearly) identical positions cannot recurse forever
+const MAX_DEPTH: u32 = 24;
+
+/// Tunable parameters of the N-body gravity model
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NBodyParams {
+    // Gravitational constant in units³ / (mass · s²)
+    pub gravitational_constant: f32,
+    // Opening angle θ: nodes with size / distance below it are treated as one body
+    pub theta: f32,
+    // Plummer softening length, keeps close encounters finite
+    pub softening: f32,
+}
+
+impl Default for NBodyParams {
+    fn default() -> Self {
+        NBodyParams {
+            gravitational_constant: 1000.0,
+            theta: 0.5,
+            softening: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    // Lower corner and edge length of the square this node covers
+    min: Vec2,
+    size: f32,
+    mass: f32,
+    // Mass-weighted position sum while building, centre of mass afterwards
+    center_of_mass: Vec2,
+    // First child index (children are stored as 4 consecutive nodes) or NONE for leaves
+    first_child: u32,
+    // Body stored in a leaf: particle index, NONE when empty, MULTIPLE at max depth
+    body: u32,
+}
+
+impl Node {
+    fn leaf(min: Vec2, size: f32) -> Self {
+        Node {
+            min,
+            size,
+            mass: 0.0,
+            center_of_mass: Vec2::zero(),
+            first_child: NONE,
+            body: NONE,
+        }
+    }
+}
+
+/// Quadtree over particle masses for O(n log n) gravity (Barnes-Hut)
+#[derive(Clone, Debug, Default)]
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    // Traversal stack for `acceleration_at`, reused across queries to avoid allocations
+    stack: Vec<u32>,
+}
+
+impl QuadTree {
+    /// Rebuild the tree from all active particles with finite mass
+    pub fn build(&mut self, particles: &[Particle]) {
+        self.nodes.clear();
+
+        let mut min = Vec2::new(f32::INFINITY, f32::INFINITY);
+        let mut max = Vec2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for particle in particles.iter().filter(|p| Self::is_source(p)) {
+            min = Vec2::new(min.x.min(particle.position.x), min.y.min(particle.position.y));
+            max = Vec2::new(max.x.max(particle.position.x), max.y.max(particle.position.y));
+        }
+        if min.x > max.x {
+            return; // No bodies
+        }
+
+        // Pad slightly so bodies on the max edge still fall inside the root
+        let size = (max.x - min.x).max(max.y - min.y).max(1.0) * 1.001;
+        self.nodes.push(Node::leaf(min, size));
+
+        for (index, particle) in particles.iter().enumerate() {
+            if Self::is_source(particle) {
+                self.insert(index as u32, particle.position, particle.mass());
+            }
+        }
+
+        // Turn mass-weighted sums into centres of mass
+        for node in &mut self.nodes {
+            if node.mass > 0.0 {
+                node.center_of_mass = node.center_of_mass * (1.0 / node.mass);
+            }
+        }
+    }
+
+    /// Gravitational acceleration on `index` at `position` from every other body
+    pub fn acceleration_at(&mut self, index: usize, position: Vec2, params: &NBodyParams) -> Vec2 {
+        let mut acceleration = Vec2::zero();
+        if self.nodes.is_empty() {
+            return acceleration;
+        }
+
+        let softening_sq = params.softening * params.softening;
+        let theta_sq = params.theta * params.theta;
+        let stack = &mut self.stack;
+        stack.clear();
+        stack.push(0);
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index as usize];
+            let is_leaf = node.first_child == NONE;
+            if node.mass <= 0.0 || (is_leaf && node.body as usize == index) {
+                continue;
+            }
+
+            let diff = node.center_of_mass - position;
+            let distance_sq = diff.x * diff.x + diff.y * diff.y;
+
+            // Far enough away (size / distance < θ) or a leaf: use the aggregate
+            if is_leaf || node.size * node.size < theta_sq * distance_sq {
+                // Unsoftened bodies at the same spot have no defined direction
+                if distance_sq + softening_sq <= 0.0 {
+                    continue;
+                }
+                let inverse = 1.0 / (distance_sq + softening_sq).sqrt();
+                acceleration = acceleration
+                    + diff * (params.gravitational_constant * node.mass * inverse * inverse * inverse);
+            } else {
+                stack.extend(node.first_child..node.first_child + 4);
+            }
+        }
+
+        acceleration
+    }
+
+    /// Static (infinite mass) and inactive particles do not attract anything; pinned and
+    /// kinematic particles keep their mass and still do
+    fn is_source(particle: &Particle) -> bool {
+        particle.active && particle.inv_mass > 0.0
+    }
+
+    fn insert(&mut self, body: u32, position: Vec2, mass: f32) {
+        let mut node_index = 0usize;
+        let mut depth = 0;
+
+        loop {
+            // Every node on the path accumulates the body's mass
+            let node = &mut self.nodes[node_index];
+            node.mass += mass;
+            node.center_of_mass = node.center_of_mass + position * mass;
+
+            if node.first_child != NONE {
+                node_index = self.child_for(node_index, position);
+                depth += 1;
+                continue;
+            }
+
+            match node.body {
+                NONE => {
+                    node.body = body;
+                    return;
+                }
+                MULTIPLE => return,
+                _ if depth >= MAX_DEPTH => {
+                    node.body = MULTIPLE;
+                    return;
+                }
+                existing => {
+                    // Split the leaf and push the existing body one level down
+                    let existing_mass = node.mass - mass;
+                    let existing_position = (node.center_of_mass - position * mass) * (1.0 / existing_mass);
+                    node.body = NONE;
+                    self.subdivide(node_index);
+
+                    let child = self.child_for(node_index, existing_position);
+                    let child_node = &mut self.nodes[child];
+                    child_node.mass = existing_mass;
+                    child_node.center_of_mass = existing_position * existing_mass;
+                    child_node.body = existing;
+
+                    node_index = self.child_for(node_index, position);
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    fn subdivide(&mut self, node_index: usize) {
+        let Node { min, size, .. } = self.nodes[node_index];
+        let half = size * 0.5;
+
+        self.nodes[node_index].first_child = self.nodes.len() as u32;
+        for quadrant in 0..4 {
+            let offset = Vec2::new(
+                if quadrant & 1 == 1 { half } else { 0.0 },
+                if quadrant & 2 == 2 { half } else { 0.0 },
+            );
+            self.nodes.push(Node::leaf(min + offset, half));
+        }
+    }
+
+    fn child_for(&self, node_index: usize, position: Vec2) -> usize {
+        let node = &self.nodes[node_index];
+        let half = node.size * 0.5;
+        let right = (position.x >= node.min.x + half) as u32;
+        let bottom = (position.y >= node.min.y + half) as u32;
+        (node.first_child + right + bottom * 2) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_bodies(count: usize, seed: u64) -> Vec<Particle> {
+        let mut rng = Rng::new(seed);
+        (0..count)
+            .map(|_| {
+                let mut particle = Particle::new(Vec2::new(rng.range(0.0, 800.0), rng.range(0.0, 600.0)), 2.0);
+                particle.set_mass(rng.range(0.5, 3.0));
+                particle
+            })
+            .collect()
+    }
+
+    fn exact_acceleration(particles: &[Particle], index: usize, params: &NBodyParams) -> Vec2 {
+        let softening_sq = params.softening * params.softening;
+        particles
+            .iter()
+            .enumerate()
+            .filter(|(j, p)| *j != index && p.active)
+            .fold(Vec2::zero(), |total, (_, p)| {
+                let diff = p.position - particles[index].position;
+                let inverse = 1.0 / (diff.x * diff.x + diff.y * diff.y + softening_sq).sqrt();
+                total + diff * (params.gravitational_constant * p.mass() * inverse * inverse * inverse)
+            })
+    }
+
+    #[test]
+    fn test_zero_theta_matches_pairwise_sum() {
+        let particles = random_bodies(200, 1);
+        let params = NBodyParams { theta: 0.0, ..NBodyParams::default() };
+        let mut tree = QuadTree::default();
+        tree.build(&particles);
+
+        for (i, particle) in particles.iter().enumerate() {
+            let exact = exact_acceleration(&particles, i, &params);
+            let approx = tree.acceleration_at(i, particle.position, &params);
+            assert!((approx - exact).length() <= exact.length() * 1e-3 + 1e-4,
+                    "Body {}: {:?} vs {:?}", i, approx, exact);
+        }
+    }
+
+    #[test]
+    fn test_opening_angle_error_is_small() {
+        let particles = random_bodies(500, 2);
+        let params = NBodyParams::default();
+        let mut tree = QuadTree::default();
+        tree.build(&particles);
+
+        let mut error_sq = 0.0;
+        let mut magnitude_sq = 0.0;
+        for (i, particle) in particles.iter().enumerate() {
+            let exact = exact_acceleration(&particles, i, &params);
+            let error = tree.acceleration_at(i, particle.position, &params) - exact;
+            error_sq += error.x * error.x + error.y * error.y;
+            magnitude_sq += exact.x * exact.x + exact.y * exact.y;
+        }
+
+        let relative_rms = (error_sq / magnitude_sq).sqrt();
+        assert!(relative_rms < 0.02, "Relative RMS error {}", relative_rms);
+    }
+
+    #[test]
+    fn test_coincident_bodies_do_not_recurse_forever() {
+        let particles: Vec<Particle> = (0..10).map(|_| Particle::new(Vec2::new(5.0, 5.0), 1.0)).collect();
+        let mut tree = QuadTree::default();
+        tree.build(&particles);
+
+        let acceleration = tree.acceleration_at(0, Vec2::new(5.0, 5.0), &NBodyParams::default());
+        assert_eq!(acceleration, Vec2::zero());
+
+        let far = tree.acceleration_at(usize::MAX, Vec2::new(105.0, 5.0), &NBodyParams::default());
+        assert!(far.x < 0.0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::materials::Materials;
+use crate::spatial_grid::SpatialGrid;
+use crate::{Particle, Vec2};
+
+/// Width of the attraction shell around each contact, as a fraction of the contact distance
+pub const COHESION_SHELL: f32 = 0.5;
+
+/// Cohesion and surface tension between particles within a shell just outside contact
+#[derive(Default)]
+pub struct Cohesion {
+    // Particle pairs closer than the neighbourhood radius, collected per pass
+    neighbours: Vec<(usize, usize)>,
+    // Per-particle surface normal estimate, pointing out of the fluid; ~0 inside a clump
+    normals: Vec<Vec2>,
+}
+
+impl Cohesion {
+    /// Add cohesion and surface-tension accelerations to `accelerations`.
+    /// `grid` must have been updated with a cell size covering the neighbourhood radius.
+    pub fn accumulate(
+        &mut self,
+        particles: &[Particle],
+        grid: &SpatialGrid,
+        materials: &Materials,
+        accelerations: &mut [Vec2],
+    ) {
+        let neighbours = &mut self.neighbours;
+        neighbours.clear();
+        grid.for_each_candidate_pair(|i, j| {
+            let (a, b) = (&particles[i], &particles[j]);
+            if !materials.pair(a.material, b.material).is_cohesive() {
+                return;
+            }
+            let distance = (a.position - b.position).length();
+            if distance > 0.0 && distance < Self::neighbourhood_radius(a, b) {
+                neighbours.push((i, j));
+            }
+        });
+
+        self.normals.clear();
+        self.normals.resize(particles.len(), Vec2::zero());
+
+        for &(i, j) in &self.neighbours {
+            let (a, b) = (&particles[i], &particles[j]);
+            let pair = materials.pair(a.material, b.material);
+            let diff = a.position - b.position;
+            let distance = diff.length();
+            let direction = diff * (1.0 / distance);
+            let outer = Self::neighbourhood_radius(a, b);
+
+            // Neighbours on one side only leave an unbalanced normal at the surface
+            let weight = 1.0 - distance / outer;
+            self.normals[i] = self.normals[i] + direction * weight;
+            self.normals[j] = self.normals[j] - direction * weight;
+
+            // Attraction peaks at contact and fades to zero at the shell's outer edge;
+            // overlapping pairs are left to the collision response
+            let contact = a.radius + b.radius;
+            if distance > contact && pair.cohesion > 0.0 {
+                let pull = pair.cohesion * (1.0 - (distance - contact) / (outer - contact));
+                let force = direction * -pull;
+                accelerations[i] = accelerations[i] + force * a.effective_inv_mass();
+                accelerations[j] = accelerations[j] - force * b.effective_inv_mass();
+            }
+        }
+
+        // Curvature term -σ(n_i - n_j): flattens bumps and pulls surface particles inward
+        for &(i, j) in &self.neighbours {
+            let (a, b) = (&particles[i], &particles[j]);
+            let tension = materials.pair(a.material, b.material).surface_tension;
+            if tension > 0.0 {
+                let force = (self.normals[i] - self.normals[j]) * -tension;
+                accelerations[i] = accelerations[i] + force * a.effective_inv_mass();
+                accelerations[j] = accelerations[j] - force * b.effective_inv_mass();
+            }
+        }
+    }
+
+    /// Distance below which a pair counts as neighbours
+    pub fn neighbourhood_radius(a: &Particle, b: &Particle) -> f32 {
+        (a.radius + b.radius) * (1.0 + COHESION_SHELL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+
+    fn run(particles: &[Particle], materials: &Materials) -> Vec<Vec2> {
+        let mut grid = SpatialGrid::new();
+        grid.update(particles, 20.0, 200.0, 200.0);
+        let mut accelerations = vec![Vec2::zero(); particles.len()];
+        Cohesion::default().accumulate(particles, &grid, materials, &mut accelerations);
+        accelerations
+    }
+
+    fn sticky(cohesion: f32, surface_tension: f32) -> (Materials, u32) {
+        let mut materials = Materials::default();
+        let id = materials.add(Material::new(cohesion, surface_tension).unwrap()).unwrap();
+        (materials, id)
+    }
+
+    #[test]
+    fn test_cohesion_acts_only_in_the_shell() {
+        let (materials, id) = sticky(100.0, 0.0);
+        let pull_at = |gap: f32| {
+            let mut particles = vec![Particle::new(Vec2::new(50.0, 50.0), 4.0), Particle::new(Vec2::new(58.0 + gap, 50.0), 4.0)];
+            particles[0].material = id;
+            particles[1].material = id;
+            run(&particles, &materials)
+        };
+
+        // Just outside contact: full attraction, equal and opposite
+        let near = pull_at(0.01);
+        assert!(near[0].x > 99.0 && (near[0].x + near[1].x).abs() < 1e-4);
+        // Half-way through the 4-unit shell: half strength
+        assert!((pull_at(2.0)[0].x - 50.0).abs() < 1e-3);
+        // Beyond the shell or overlapping: nothing
+        assert_eq!(pull_at(4.5)[0], Vec2::zero());
+        assert_eq!(pull_at(-1.0)[0], Vec2::zero());
+
+        // Inert particles do not stick, to each other or to a sticky one
+        let mut particles = vec![Particle::new(Vec2::new(50.0, 50.0), 4.0), Particle::new(Vec2::new(58.5, 50.0), 4.0)];
+        assert_eq!(run(&particles, &materials)[0], Vec2::zero());
+        particles[0].material = id;
+        assert_eq!(run(&particles, &materials)[0], Vec2::zero());
+    }
+
+    #[test]
+    fn test_surface_tension_pulls_edges_inward() {
+        let (materials, id) = sticky(0.0, 100.0);
+        // A row of five touching particles: the ends are surface particles
+        let particles: Vec<Particle> = (0..5)
+            .map(|i| {
+                let mut particle = Particle::new(Vec2::new(50.0 + i as f32 * 8.0, 50.0), 4.0);
+                particle.material = id;
+                particle
+            })
+            .collect();
+        let accelerations = run(&particles, &materials);
+
+        assert!(accelerations[0].x > 0.0);
+        assert!(accelerations[4].x < 0.0);
+        assert!(accelerations[2].length() < 1e-4);
+        let total = accelerations.iter().fold(Vec2::zero(), |sum, &a| sum + a);
+        assert!(total.length() < 1e-3);
+    }
+}
@@ -0,0 +1,354 @@
+use crate::config::validate_range;
+use crate::materials::{Material, MAX_FRICTION};
+use crate::{Particle, Vec2};
+
+/// Most vertices accepted in one polygon collider
+pub const MAX_POLYGON_POINTS: usize = 1024;
+
+/// Gap left between a particle and a collider after a push-out, like the container walls' buffer
+const SEPARATION: f32 = 0.01;
+
+/// Kind tags written to the geometry buffer
+const SEGMENT_TAG: f32 = 0.0;
+const POLYGON_TAG: f32 = 1.0;
+const CIRCLE_TAG: f32 = 2.0;
+
+/// Closest point to `point` on the segment from `a` to `b`
+fn closest_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let edge = b - a;
+    let length_sq = edge.dot(edge);
+    if length_sq <= f32::EPSILON {
+        return a;
+    }
+    a + edge * ((point - a).dot(edge) / length_sq).clamp(0.0, 1.0)
+}
+
+/// Static collision geometry
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Segment { a: Vec2, b: Vec2 },
+    // Closed outline, convex or concave, in either winding order
+    Polygon { points: Vec<Vec2> },
+    Circle { center: Vec2, radius: f32 },
+}
+
+/// A contact found between a particle and a collider
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    // Unit direction that pushes the particle out of the collider
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+impl Shape {
+    /// Axis-aligned bounds as (min, max)
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match self {
+            Shape::Segment { a, b } => (Vec2::new(a.x.min(b.x), a.y.min(b.y)), Vec2::new(a.x.max(b.x), a.y.max(b.y))),
+            Shape::Polygon { points } => points.iter().fold((points[0], points[0]), |(min, max), p| {
+                (Vec2::new(min.x.min(p.x), min.y.min(p.y)), Vec2::new(max.x.max(p.x), max.y.max(p.y)))
+            }),
+            Shape::Circle { center, radius } => {
+                (*center - Vec2::new(*radius, *radius), *center + Vec2::new(*radius, *radius))
+            }
+        }
+    }
+
+    /// Contact for a particle of `radius` now at `position` that was at `previous` last step
+    pub fn contact(&self, position: Vec2, previous: Vec2, radius: f32) -> Option<Contact> {
+        match self {
+            Shape::Segment { a, b } => Self::segment_contact(*a, *b, position, previous, radius),
+            Shape::Polygon { points } => Self::polygon_contact(points, position, previous, radius),
+            Shape::Circle { center, radius: circle_radius } => {
+                let offset = position - *center;
+                let distance = offset.length();
+                let reach = circle_radius + radius;
+                if distance >= reach {
+                    return None;
+                }
+                let normal = if distance > f32::EPSILON { offset * (1.0 / distance) } else { Vec2::new(0.0, -1.0) };
+                Some(Contact { normal, depth: reach - distance })
+            }
+        }
+    }
+
+    fn segment_contact(a: Vec2, b: Vec2, position: Vec2, previous: Vec2, radius: f32) -> Option<Contact> {
+        let edge = b - a;
+        let length = edge.length();
+
+        // A fast particle that crossed the line since last step goes back to the side it came from
+        let (side_now, side_before) = (edge.cross(position - a), edge.cross(previous - a));
+        if length > f32::EPSILON && side_now * side_before < 0.0 {
+            let motion = position - previous;
+            let t = (a - previous).cross(edge) / motion.cross(edge);
+            let u = (a - previous).cross(motion) / motion.cross(edge);
+            if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                let line_normal = Vec2::new(-edge.y, edge.x) * (side_before.signum() / length);
+                return Some(Contact { normal: line_normal, depth: side_now.abs() / length + radius });
+            }
+        }
+
+        let offset = position - closest_on_segment(position, a, b);
+        let distance = offset.length();
+        if distance >= radius {
+            return None;
+        }
+        let normal = if distance > f32::EPSILON {
+            offset * (1.0 / distance)
+        } else if length > f32::EPSILON {
+            Vec2::new(-edge.y, edge.x) * (side_before.signum() / length)
+        } else {
+            Vec2::new(0.0, -1.0)
+        };
+        Some(Contact { normal, depth: radius - distance })
+    }
+
+    fn polygon_contact(points: &[Vec2], position: Vec2, previous: Vec2, radius: f32) -> Option<Contact> {
+        // Nearest edge point, and whether the centre is inside (even-odd rule)
+        let mut nearest = points[0];
+        let mut nearest_distance_sq = f32::INFINITY;
+        let mut inside = false;
+        for (index, &a) in points.iter().enumerate() {
+            let b = points[(index + 1) % points.len()];
+            let point = closest_on_segment(position, a, b);
+            let distance_sq = (position - point).dot(position - point);
+            if distance_sq < nearest_distance_sq {
+                nearest = point;
+                nearest_distance_sq = distance_sq;
+            }
+            if (a.y > position.y) != (b.y > position.y) && position.x < a.x + (position.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+                inside = !inside;
+            }
+        }
+
+        let distance = nearest_distance_sq.sqrt();
+        if inside {
+            // Out through the nearest edge, falling back to where the particle came from
+            let normal = if distance > f32::EPSILON { (nearest - position) * (1.0 / distance) } else { (previous - position).normalize() };
+            return Some(Contact { normal, depth: distance + radius });
+        }
+        if distance >= radius || distance <= f32::EPSILON {
+            return None;
+        }
+        Some(Contact { normal: (position - nearest) * (1.0 / distance), depth: radius - distance })
+    }
+
+    /// Append this shape to a geometry buffer as [tag, value count, values...]
+    fn write_geometry(&self, buffer: &mut Vec<f32>) {
+        match self {
+            Shape::Segment { a, b } => buffer.extend_from_slice(&[SEGMENT_TAG, 4.0, a.x, a.y, b.x, b.y]),
+            Shape::Polygon { points } => {
+                buffer.extend_from_slice(&[POLYGON_TAG, (points.len() * 2) as f32]);
+                for point in points {
+                    buffer.extend_from_slice(&[point.x, point.y]);
+                }
+            }
+            Shape::Circle { center, radius } => buffer.extend_from_slice(&[CIRCLE_TAG, 3.0, center.x, center.y, *radius]),
+        }
+    }
+}
+
+/// A static obstacle with its own bounce and friction
+#[derive(Clone, Debug)]
+pub struct Collider {
+    shape: Shape,
+    // Fraction of the normal speed kept when a particle bounces off
+    restitution: f32,
+    // Friction against particles, using the material model with equal static and kinetic coefficients
+    surface: Material,
+    bounds: (Vec2, Vec2),
+}
+
+impl Collider {
+    /// Build a collider, validating its shape and surface parameters
+    pub fn new(shape: Shape, restitution: f32, friction: f32) -> Result<Collider, String> {
+        let finite = |p: &Vec2| p.x.is_finite() && p.y.is_finite();
+        match &shape {
+            Shape::Segment { a, b } => {
+                if !(finite(a) && finite(b)) {
+                    return Err("Segment end points must be finite".to_string());
+                }
+            }
+            Shape::Polygon { points } => {
+                if points.len() < 3 || points.len() > MAX_POLYGON_POINTS {
+                    return Err(format!("A polygon needs 3 to {} points, got {}", MAX_POLYGON_POINTS, points.len()));
+                }
+                if !points.iter().all(finite) {
+                    return Err("Polygon points must be finite".to_string());
+                }
+                let twice_area: f32 = (0..points.len()).map(|i| points[i].cross(points[(i + 1) % points.len()])).sum();
+                if twice_area.abs() <= f32::EPSILON {
+                    return Err("Polygon must enclose an area".to_string());
+                }
+            }
+            Shape::Circle { center, radius } => {
+                if !finite(center) {
+                    return Err("Circle centre must be finite".to_string());
+                }
+                validate_range("radius", *radius, 0.5, 1e5)?;
+            }
+        }
+
+        let restitution = validate_range("restitution", restitution, 0.0, 1.0)?;
+        let friction = validate_range("friction", friction, 0.0, MAX_FRICTION)?;
+        let surface = Material::default().with_friction(friction, friction, 0.0)?;
+        let bounds = shape.bounds();
+        Ok(Collider { shape, restitution, surface, bounds })
+    }
+
+    /// Push a particle out of the collider, bounce its normal velocity and apply friction
+    /// to its tangential velocity. Velocity is the displacement position - position_old.
+    fn resolve(&self, particle: &mut Particle) {
+        let (min, max) = self.bounds;
+        let (position, radius) = (particle.position, particle.radius);
+        let reach = (position - particle.position_old).length() + radius;
+        if position.x + reach < min.x || position.x - reach > max.x || position.y + reach < min.y || position.y - reach > max.y {
+            return;
+        }
+        let Some(contact) = self.shape.contact(position, particle.position_old, radius) else {
+            return;
+        };
+
+        let velocity = particle.position - particle.position_old;
+        particle.position = particle.position + contact.normal * (contact.depth + SEPARATION);
+
+        let normal_speed = velocity.dot(contact.normal);
+        let tangential = velocity - contact.normal * normal_speed;
+        let tangential = tangential - self.surface.friction_correction(tangential, contact.depth);
+        let normal_speed = if normal_speed < 0.0 { -normal_speed * self.restitution } else { normal_speed };
+        particle.position_old = particle.position - (tangential + contact.normal * normal_speed);
+    }
+}
+
+/// Static colliders addressed by ID
+#[derive(Clone, Debug, Default)]
+pub struct Colliders {
+    colliders: Vec<(u32, Collider)>,
+    next_id: u32,
+}
+
+impl Colliders {
+    /// Store a collider and return its ID
+    pub fn add(&mut self, collider: Collider) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.colliders.push((id, collider));
+        id
+    }
+
+    /// Remove a collider; returns false if the ID is unknown
+    pub fn remove(&mut self, id: u32) -> bool {
+        let count = self.colliders.len();
+        self.colliders.retain(|(collider_id, _)| *collider_id != id);
+        self.colliders.len() != count
+    }
+
+    /// Remove every collider
+    pub fn clear(&mut self) {
+        self.colliders.clear();
+    }
+
+    /// Number of stored colliders
+    pub fn len(&self) -> usize {
+        self.colliders.len()
+    }
+
+    /// Whether no colliders are stored
+    pub fn is_empty(&self) -> bool {
+        self.colliders.is_empty()
+    }
+
+    /// Resolve contacts between one particle and every collider
+    pub fn resolve(&self, particle: &mut Particle) {
+        for (_, collider) in &self.colliders {
+            collider.resolve(particle);
+        }
+    }
+
+    /// Geometry of every collider for drawing: [tag, value count, values...] per collider,
+    /// where tag 0 is a segment [ax, ay, bx, by], 1 a polygon [x1, y1, x2, y2, ...] and
+    /// 2 a circle [cx, cy, radius]
+    pub fn write_geometry(&self, buffer: &mut Vec<f32>) {
+        buffer.clear();
+        for (_, collider) in &self.colliders {
+            collider.shape.write_geometry(buffer);
+        }
+    }
+
+    /// IDs in the same order as the geometry buffer
+    pub fn write_ids(&self, buffer: &mut Vec<u32>) {
+        buffer.clear();
+        buffer.extend(self.colliders.iter().map(|(id, _)| *id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moving(from: Vec2, to: Vec2, radius: f32) -> Particle {
+        let mut particle = Particle::new(to, radius);
+        particle.position_old = from;
+        particle
+    }
+
+    #[test]
+    fn test_segment_bounces_with_restitution() {
+        let floor = Shape::Segment { a: Vec2::new(0.0, 100.0), b: Vec2::new(200.0, 100.0) };
+        let collider = Collider::new(floor, 0.5, 0.0).unwrap();
+        let mut particle = moving(Vec2::new(50.0, 93.0), Vec2::new(52.0, 97.0), 5.0);
+        collider.resolve(&mut particle);
+
+        assert!((particle.position.y - (95.0 - SEPARATION)).abs() < 1e-4);
+        let velocity = particle.position - particle.position_old;
+        assert!((velocity.y + 2.0).abs() < 1e-4, "Velocity {:?}", velocity);
+        assert!((velocity.x - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fast_particle_cannot_tunnel_through_a_segment() {
+        let wall = Shape::Segment { a: Vec2::new(100.0, 0.0), b: Vec2::new(100.0, 200.0) };
+        let collider = Collider::new(wall, 0.0, 0.0).unwrap();
+        let mut particle = moving(Vec2::new(90.0, 50.0), Vec2::new(130.0, 50.0), 2.0);
+        collider.resolve(&mut particle);
+
+        assert!(particle.position.x <= 98.0, "Position {:?}", particle.position);
+    }
+
+    #[test]
+    fn test_concave_polygon_pushes_out_through_nearest_edge() {
+        // An L shape: the notch at the top right is outside
+        let points = [(0.0, 0.0), (40.0, 0.0), (40.0, 20.0), (20.0, 20.0), (20.0, 40.0), (0.0, 40.0)]
+            .iter()
+            .map(|&(x, y)| Vec2::new(x, y))
+            .collect();
+        let shape = Shape::Polygon { points };
+
+        let inside = shape.contact(Vec2::new(35.0, 17.0), Vec2::new(35.0, 17.0), 1.0).unwrap();
+        assert_eq!(inside.normal, Vec2::new(0.0, 1.0));
+        assert!((inside.depth - 4.0).abs() < 1e-4);
+        assert!(shape.contact(Vec2::new(30.0, 30.0), Vec2::new(30.0, 30.0), 1.0).is_none());
+        let grazing = shape.contact(Vec2::new(30.0, 21.0), Vec2::new(30.0, 21.0), 2.0).unwrap();
+        assert_eq!(grazing.normal, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn test_friction_slows_sliding() {
+        let floor = Shape::Circle { center: Vec2::new(100.0, 200.0), radius: 100.0 };
+        let slippery = Collider::new(floor.clone(), 0.0, 0.0).unwrap();
+        let rough = Collider::new(floor, 0.0, 0.5).unwrap();
+        let slide = |collider: &Collider| {
+            let mut particle = moving(Vec2::new(97.0, 94.0), Vec2::new(100.0, 96.0), 5.0);
+            collider.resolve(&mut particle);
+            (particle.position - particle.position_old).x
+        };
+
+        assert!((slide(&slippery) - 3.0).abs() < 1e-4);
+        assert!(slide(&rough) < 3.0);
+        assert!(Collider::new(Shape::Circle { center: Vec2::zero(), radius: 1.0 }, 1.5, 0.0).is_err());
+        let error = Collider::new(Shape::Circle { center: Vec2::zero(), radius: 1.0 }, 0.5, -1.0).unwrap_err();
+        assert!(error.starts_with("friction "), "Error {}", error);
+        let line = Shape::Polygon { points: vec![Vec2::zero(), Vec2::new(1.0, 1.0), Vec2::new(2.0, 2.0)] };
+        assert!(Collider::new(line, 0.5, 0.5).is_err());
+    }
+}
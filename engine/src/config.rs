@@ -0,0 +1,153 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Vec2;
+
+/// Largest gravity component accepted, in pixels per second squared
+const MAX_GRAVITY: f32 = 10_000.0;
+
+/// Runtime-tunable physics parameters, shared between the solver and JS presets.
+/// Every setter validates its input and leaves the config untouched on error.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverConfig {
+    gravity_x: f32,
+    gravity_y: f32,
+    boundary_damping: f32,
+    restitution: f32,
+    impulse_factor: f32,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            gravity_x: 0.0,
+            gravity_y: 150.0, // Normal gravity for regular ball physics
+            boundary_damping: 0.85, // Energy loss on collision for regular ball behavior
+            restitution: 0.3, // Normal bounce factor for regular balls
+            impulse_factor: 0.3, // Normal impulse strength
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SolverConfig {
+    /// Create a config holding the default regular ball physics values
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SolverConfig {
+        SolverConfig::default()
+    }
+
+    /// Horizontal gravity in pixels per second squared
+    #[wasm_bindgen(getter)]
+    pub fn gravity_x(&self) -> f32 {
+        self.gravity_x
+    }
+
+    /// Set horizontal gravity (within ±10000)
+    #[wasm_bindgen(setter)]
+    pub fn set_gravity_x(&mut self, value: f32) -> Result<(), String> {
+        self.gravity_x = validate_range("gravity_x", value, -MAX_GRAVITY, MAX_GRAVITY)?;
+        Ok(())
+    }
+
+    /// Vertical gravity in pixels per second squared (positive is down)
+    #[wasm_bindgen(getter)]
+    pub fn gravity_y(&self) -> f32 {
+        self.gravity_y
+    }
+
+    /// Set vertical gravity (within ±10000)
+    #[wasm_bindgen(setter)]
+    pub fn set_gravity_y(&mut self, value: f32) -> Result<(), String> {
+        self.gravity_y = validate_range("gravity_y", value, -MAX_GRAVITY, MAX_GRAVITY)?;
+        Ok(())
+    }
+
+    /// Fraction of velocity kept when bouncing off the container walls
+    #[wasm_bindgen(getter)]
+    pub fn boundary_damping(&self) -> f32 {
+        self.boundary_damping
+    }
+
+    /// Set wall bounce damping (within [0, 1])
+    #[wasm_bindgen(setter)]
+    pub fn set_boundary_damping(&mut self, value: f32) -> Result<(), String> {
+        self.boundary_damping = validate_range("boundary_damping", value, 0.0, 1.0)?;
+        Ok(())
+    }
+
+    /// Bounce factor for particle-particle collisions
+    #[wasm_bindgen(getter)]
+    pub fn restitution(&self) -> f32 {
+        self.restitution
+    }
+
+    /// Set particle restitution (within [0, 1])
+    #[wasm_bindgen(setter)]
+    pub fn set_restitution(&mut self, value: f32) -> Result<(), String> {
+        self.restitution = validate_range("restitution", value, 0.0, 1.0)?;
+        Ok(())
+    }
+
+    /// Share of the collision impulse applied per contact
+    #[wasm_bindgen(getter)]
+    pub fn impulse_factor(&self) -> f32 {
+        self.impulse_factor
+    }
+
+    /// Set the collision impulse factor (within [0, 1])
+    #[wasm_bindgen(setter)]
+    pub fn set_impulse_factor(&mut self, value: f32) -> Result<(), String> {
+        self.impulse_factor = validate_range("impulse_factor", value, 0.0, 1.0)?;
+        Ok(())
+    }
+}
+
+impl SolverConfig {
+    /// Gravity as a vector
+    pub fn gravity(&self) -> Vec2 {
+        Vec2::new(self.gravity_x, self.gravity_y)
+    }
+}
+
+/// Check that a value is finite and inside [min, max]
+pub(crate) fn validate_range(name: &str, value: f32, min: f32, max: f32) -> Result<f32, String> {
+    if value.is_finite() && value >= min && value <= max {
+        Ok(value)
+    } else {
+        Err(format!("{} must be within [{}, {}], got {}", name, min, max, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_matches_regular_ball_physics() {
+        let config = SolverConfig::new();
+        assert_eq!(config.gravity(), Vec2::new(0.0, 150.0));
+        assert_eq!(config.boundary_damping(), 0.85);
+        assert_eq!(config.restitution(), 0.3);
+        assert_eq!(config.impulse_factor(), 0.3);
+    }
+
+    #[test]
+    fn test_setters_reject_out_of_range_values() {
+        let mut config = SolverConfig::new();
+
+        assert!(config.set_boundary_damping(1.5).is_err());
+        assert!(config.set_restitution(-0.1).is_err());
+        assert!(config.set_impulse_factor(f32::NAN).is_err());
+        assert!(config.set_gravity_y(f32::INFINITY).is_err());
+        assert!(config.set_gravity_x(20_000.0).is_err());
+
+        // Rejected values leave the config unchanged
+        assert_eq!(config, SolverConfig::new());
+
+        config.set_gravity_y(-50.0).unwrap();
+        config.set_restitution(1.0).unwrap();
+        assert_eq!(config.gravity_y(), -50.0);
+        assert_eq!(config.restitution(), 1.0);
+    }
+}
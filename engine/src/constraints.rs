@@ -0,0 +1,323 @@
+use crate::Particle;
+
+/// Largest accepted spring damping coefficient
+const MAX_DAMPING: f32 = 1e4;
+
+/// Check that a link stiffness is positive; `Infinity` is allowed and means rigid
+pub fn validate_stiffness(stiffness: f32) -> Result<f32, String> {
+    if stiffness.is_nan() || stiffness <= 0.0 {
+        return Err(format!("Stiffness must be positive, got {}", stiffness));
+    }
+    Ok(stiffness)
+}
+
+/// A link between two particles, solved with XPBD (extended position based dynamics)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Constraint {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    // Inverse stiffness; 0 makes the link rigid
+    pub compliance: f32,
+    // Damping coefficient along the link, only meaningful for compliant links
+    pub damping: f32,
+    // The link breaks when stretched beyond this fraction of its rest length, or when the
+    // force it carries exceeds `break_force`; `Infinity` never breaks
+    pub break_stretch: f32,
+    pub break_force: f32,
+    // Accumulated Lagrange multiplier for the current substep
+    lambda: f32,
+    // Relative stretch before relaxation and force carried during the last substep
+    // (positive in tension, negative in compression)
+    stretch: f32,
+    stress: f32,
+}
+
+impl Constraint {
+    /// Build a link; `Infinity` stiffness makes it rigid
+    pub fn new(a: usize, b: usize, rest_length: f32, stiffness: f32, damping: f32) -> Result<Constraint, String> {
+        if a == b {
+            return Err(format!("Cannot connect particle {} to itself", a));
+        }
+        if !(rest_length.is_finite() && rest_length >= 0.0) {
+            return Err(format!("Rest length must be finite and non-negative, got {}", rest_length));
+        }
+        validate_stiffness(stiffness)?;
+        if !(damping.is_finite() && (0.0..=MAX_DAMPING).contains(&damping)) {
+            return Err(format!("Damping must be within [0, {}], got {}", MAX_DAMPING, damping));
+        }
+
+        Ok(Constraint {
+            a,
+            b,
+            rest_length,
+            compliance: 1.0 / stiffness,
+            damping,
+            break_stretch: f32::INFINITY,
+            break_force: f32::INFINITY,
+            lambda: 0.0,
+            stretch: 0.0,
+            stress: 0.0,
+        })
+    }
+
+    /// Copy of this link with new break thresholds; `Infinity` disables either one
+    pub fn with_break_thresholds(self, max_stretch: f32, max_force: f32) -> Result<Constraint, String> {
+        if max_stretch.is_nan() || max_stretch <= 0.0 {
+            return Err(format!("Break stretch must be positive, got {}", max_stretch));
+        }
+        if max_force.is_nan() || max_force <= 0.0 {
+            return Err(format!("Break force must be positive, got {}", max_force));
+        }
+        Ok(Constraint { break_stretch: max_stretch, break_force: max_force, ..self })
+    }
+
+    /// Force carried during the last substep, positive in tension
+    pub fn stress(&self) -> f32 {
+        self.stress
+    }
+
+    /// Whether the last substep pulled the link past either break threshold
+    fn is_broken(&self) -> bool {
+        self.stretch > self.break_stretch || self.stress.abs() > self.break_force
+    }
+
+    /// Move both endpoints towards the rest length. `dt` is the substep length.
+    fn solve(&mut self, particles: &mut [Particle], dt: f32) {
+        let (pa, pb) = (&particles[self.a], &particles[self.b]);
+        if !(pa.active && pb.active) {
+            return;
+        }
+        let weight_sum = pa.effective_inv_mass() + pb.effective_inv_mass();
+        let diff = pa.position - pb.position;
+        let distance = diff.length();
+        if weight_sum <= 0.0 || distance <= f32::EPSILON {
+            return;
+        }
+
+        let normal = diff * (1.0 / distance);
+        let error = distance - self.rest_length;
+        let alpha = self.compliance / (dt * dt);
+        let gamma = self.compliance * self.damping / dt;
+
+        // Relative motion along the link since the start of the substep, for damping
+        let motion = (pa.position - pa.position_old) - (pb.position - pb.position_old);
+        let stretch_rate = motion.x * normal.x + motion.y * normal.y;
+
+        let delta = (-error - alpha * self.lambda - gamma * stretch_rate) / ((1.0 + gamma) * weight_sum + alpha);
+        self.lambda += delta;
+
+        let correction = normal * delta;
+        let (wa, wb) = (pa.effective_inv_mass(), pb.effective_inv_mass());
+        particles[self.a].position = particles[self.a].position + correction * wa;
+        particles[self.b].position = particles[self.b].position - correction * wb;
+    }
+}
+
+/// Constraints addressed by ID, solved on every relaxation pass
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    constraints: Vec<(u32, Constraint)>,
+    next_id: u32,
+}
+
+impl Constraints {
+    /// Store a constraint and return its ID
+    pub fn add(&mut self, constraint: Constraint) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.constraints.push((id, constraint));
+        id
+    }
+
+    /// Remove a constraint; returns false if the ID is unknown
+    pub fn remove(&mut self, id: u32) -> bool {
+        let count = self.constraints.len();
+        self.constraints.retain(|(constraint_id, _)| *constraint_id != id);
+        self.constraints.len() != count
+    }
+
+    /// Remove every constraint attached to a particle slot
+    pub fn remove_particle(&mut self, index: usize) {
+        self.constraints.retain(|(_, c)| c.a != index && c.b != index);
+    }
+
+    /// Remove constraints attached to inactive slots, which may be reused by new particles
+    pub fn remove_inactive(&mut self, particles: &[Particle]) {
+        self.constraints.retain(|(_, c)| {
+            particles.get(c.a).is_some_and(|p| p.active) && particles.get(c.b).is_some_and(|p| p.active)
+        });
+    }
+
+    /// Remove every constraint
+    pub fn clear(&mut self) {
+        self.constraints.clear();
+    }
+
+    /// Number of stored constraints
+    pub fn len(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Whether no constraints are stored
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// Look up a constraint by ID
+    pub fn get(&self, id: u32) -> Option<&Constraint> {
+        self.constraints.iter().find(|(constraint_id, _)| *constraint_id == id).map(|(_, c)| c)
+    }
+
+    /// Replace the constraint stored under an ID
+    pub fn replace(&mut self, id: u32, constraint: Constraint) -> Result<(), String> {
+        let (_, slot) = self
+            .constraints
+            .iter_mut()
+            .find(|(constraint_id, _)| *constraint_id == id)
+            .ok_or_else(|| format!("Unknown constraint {}", id))?;
+        *slot = constraint;
+        Ok(())
+    }
+
+    /// Reset the XPBD multipliers and measure how far each link was pulled by the
+    /// unconstrained motion; call at the start of every substep
+    pub fn begin_step(&mut self, particles: &[Particle]) {
+        for (_, constraint) in &mut self.constraints {
+            constraint.lambda = 0.0;
+            let distance = (particles[constraint.a].position - particles[constraint.b].position).length();
+            constraint.stretch = if constraint.rest_length > 0.0 {
+                distance / constraint.rest_length - 1.0
+            } else {
+                0.0
+            };
+        }
+    }
+
+    /// Record each link's force from its multiplier, then remove links past their break
+    /// thresholds, appending `[id, a, b]` for each one to `broken`
+    pub fn end_step(&mut self, dt: f32, broken: &mut Vec<u32>) {
+        if dt <= 0.0 {
+            return;
+        }
+        let inverse_dt_sq = 1.0 / (dt * dt);
+        self.constraints.retain_mut(|(id, constraint)| {
+            constraint.stress = -constraint.lambda * inverse_dt_sq;
+            if constraint.is_broken() {
+                broken.extend_from_slice(&[*id, constraint.a as u32, constraint.b as u32]);
+                return false;
+            }
+            true
+        });
+    }
+
+    /// One Gauss-Seidel pass over all constraints
+    pub fn solve(&mut self, particles: &mut [Particle], dt: f32) {
+        for (_, constraint) in &mut self.constraints {
+            constraint.solve(particles, dt);
+        }
+    }
+
+    /// Current endpoint positions for drawing, [ax, ay, bx, by] per constraint
+    pub fn write_endpoints(&self, particles: &[Particle], buffer: &mut Vec<f32>) {
+        buffer.clear();
+        for (_, constraint) in &self.constraints {
+            let (a, b) = (particles[constraint.a].position, particles[constraint.b].position);
+            buffer.extend_from_slice(&[a.x, a.y, b.x, b.y]);
+        }
+    }
+
+    /// Force carried by each link during the last substep, in the same order as the endpoint buffer
+    pub fn write_stresses(&self, buffer: &mut Vec<f32>) {
+        buffer.clear();
+        buffer.extend(self.constraints.iter().map(|(_, c)| c.stress));
+    }
+
+    /// IDs in the same order as the endpoint buffer
+    pub fn write_ids(&self, buffer: &mut Vec<u32>) {
+        buffer.clear();
+        buffer.extend(self.constraints.iter().map(|(id, _)| *id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vec2;
+
+    fn pair(distance: f32) -> Vec<Particle> {
+        vec![Particle::new(Vec2::new(0.0, 0.0), 1.0), Particle::new(Vec2::new(distance, 0.0), 1.0)]
+    }
+
+    #[test]
+    fn test_rigid_link_restores_length_in_one_pass() {
+        let mut particles = pair(15.0);
+        particles[1].set_mass(3.0);
+        let mut constraints = Constraints::default();
+        constraints.add(Constraint::new(0, 1, 10.0, f32::INFINITY, 0.0).unwrap());
+        constraints.begin_step(&particles);
+        constraints.solve(&mut particles, 1.0 / 60.0);
+
+        assert!(((particles[1].position - particles[0].position).length() - 10.0).abs() < 1e-4);
+        // The lighter particle moves three times as far
+        assert!((particles[0].position.x - 3.75).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_soft_spring_only_partially_corrects() {
+        let mut particles = pair(15.0);
+        let mut constraints = Constraints::default();
+        constraints.add(Constraint::new(0, 1, 10.0, 100.0, 0.0).unwrap());
+        constraints.begin_step(&particles);
+        constraints.solve(&mut particles, 1.0 / 60.0);
+
+        let length = (particles[1].position - particles[0].position).length();
+        assert!(length < 15.0 && length > 10.0, "Length {}", length);
+    }
+
+    #[test]
+    fn test_removal_by_id_and_particle() {
+        let mut constraints = Constraints::default();
+        let first = constraints.add(Constraint::new(0, 1, 1.0, f32::INFINITY, 0.0).unwrap());
+        constraints.add(Constraint::new(1, 2, 1.0, f32::INFINITY, 0.0).unwrap());
+        constraints.add(Constraint::new(3, 4, 1.0, f32::INFINITY, 0.0).unwrap());
+
+        assert!(constraints.remove(first));
+        assert!(!constraints.remove(first));
+        constraints.remove_particle(2);
+        assert_eq!(constraints.len(), 1);
+
+        let mut particles: Vec<Particle> = (0..5).map(|_| Particle::new(Vec2::zero(), 1.0)).collect();
+        constraints.remove_inactive(&particles);
+        assert_eq!(constraints.len(), 1);
+        particles[4].active = false;
+        constraints.remove_inactive(&particles);
+        assert!(constraints.is_empty());
+
+        assert!(Constraint::new(1, 1, 1.0, 1.0, 0.0).is_err());
+        assert!(Constraint::new(0, 1, -1.0, 1.0, 0.0).is_err());
+        assert!(Constraint::new(0, 1, 1.0, 0.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_overstretched_link_breaks() {
+        let mut particles = pair(15.0);
+        particles.extend(pair(15.0));
+        let mut constraints = Constraints::default();
+        let link = Constraint::new(0, 1, 10.0, f32::INFINITY, 0.0).unwrap();
+        let weak = constraints.add(link.with_break_thresholds(0.2, f32::INFINITY).unwrap());
+        let strong = constraints.add(Constraint { a: 2, b: 3, ..link.with_break_thresholds(0.8, f32::INFINITY).unwrap() });
+        let mut broken = Vec::new();
+
+        constraints.begin_step(&particles);
+        constraints.solve(&mut particles, 0.1);
+        constraints.end_step(0.1, &mut broken);
+
+        assert_eq!(broken, vec![weak, 0, 1]);
+        assert_eq!(constraints.len(), 1);
+        // The surviving link was in tension
+        assert!(constraints.get(strong).unwrap().stress() > 0.0);
+        assert!(link.with_break_thresholds(0.0, 1.0).is_err());
+        assert!(link.with_break_thresholds(1.0, f32::NAN).is_err());
+    }
+}
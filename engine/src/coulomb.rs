@@ -0,0 +1,112 @@
+use crate::spatial_grid::SpatialGrid;
+use crate::{Particle, Vec2};
+
+/// Largest accepted charge magnitude per particle
+pub const MAX_CHARGE: f32 = 1000.0;
+
+/// Tunable parameters of the short-range Coulomb interaction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoulombParams {
+    // Coulomb constant k in units³ · mass / (charge² · s²)
+    pub constant: f32,
+    // Interaction range; pairs further apart than this do not interact
+    pub cutoff: f32,
+}
+
+impl Default for CoulombParams {
+    fn default() -> Self {
+        CoulombParams {
+            constant: 100_000.0,
+            cutoff: 60.0,
+        }
+    }
+}
+
+impl CoulombParams {
+    /// Force on a particle with charge `q_a` from one with charge `q_b`, where
+    /// `diff` points from b to a. The force is shifted so it reaches zero exactly at
+    /// the cutoff instead of jumping, and the distance is floored at the contact
+    /// distance so overlapping pairs stay finite.
+    pub fn force(&self, q_a: f32, q_b: f32, diff: Vec2, contact_distance: f32) -> Vec2 {
+        let distance = diff.length();
+        if distance >= self.cutoff || distance <= 0.0 {
+            return Vec2::zero();
+        }
+
+        let r = distance.max(contact_distance).min(self.cutoff);
+        let magnitude = self.constant * q_a * q_b * (1.0 / (r * r) - 1.0 / (self.cutoff * self.cutoff));
+        diff * (magnitude / distance)
+    }
+
+    /// Add the Coulomb acceleration of every charged pair within the cutoff to
+    /// `accelerations`. `grid` must have been updated with a cell size of at least the cutoff.
+    pub fn accumulate(&self, particles: &[Particle], grid: &SpatialGrid, accelerations: &mut [Vec2]) {
+        grid.for_each_candidate_pair(|i, j| {
+            let (a, b) = (&particles[i], &particles[j]);
+            if a.charge == 0.0 || b.charge == 0.0 {
+                return;
+            }
+
+            // Equal and opposite, so the pair conserves momentum
+            let force = self.force(a.charge, b.charge, a.position - b.position, a.radius + b.radius);
+            accelerations[i] = accelerations[i] + force * a.effective_inv_mass();
+            accelerations[j] = accelerations[j] - force * b.effective_inv_mass();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_charges_repel_and_opposite_attract() {
+        let params = CoulombParams::default();
+        let diff = Vec2::new(20.0, 0.0);
+
+        assert!(params.force(1.0, 1.0, diff, 8.0).x > 0.0);
+        assert!(params.force(-1.0, -1.0, diff, 8.0).x > 0.0);
+        assert!(params.force(1.0, -1.0, diff, 8.0).x < 0.0);
+        assert_eq!(params.force(0.0, 1.0, diff, 8.0), Vec2::zero());
+    }
+
+    #[test]
+    fn test_force_vanishes_at_cutoff() {
+        let params = CoulombParams { constant: 1.0, cutoff: 50.0 };
+
+        assert_eq!(params.force(1.0, 1.0, Vec2::new(50.0, 0.0), 8.0), Vec2::zero());
+        assert!(params.force(1.0, 1.0, Vec2::new(49.9, 0.0), 8.0).x < 1e-5);
+
+        // Overlapping pairs are treated as touching
+        let touching = params.force(1.0, 1.0, Vec2::new(8.0, 0.0), 8.0);
+        assert_eq!(params.force(1.0, 1.0, Vec2::new(2.0, 0.0), 8.0).x, touching.x);
+    }
+
+    #[test]
+    fn test_accumulate_matches_direct_sum() {
+        let params = CoulombParams { constant: 1000.0, cutoff: 30.0 };
+        let mut particles: Vec<Particle> = (0..6)
+            .map(|i| Particle::new(Vec2::new(20.0 + i as f32 * 12.0, 40.0 + (i % 2) as f32 * 7.0), 3.0))
+            .collect();
+        for (i, particle) in particles.iter_mut().enumerate() {
+            particle.charge = if i % 3 == 0 { -2.0 } else { 1.0 };
+        }
+        particles[4].set_mass(2.0);
+
+        let mut grid = SpatialGrid::new();
+        grid.update(&particles, params.cutoff, 200.0, 200.0);
+        let mut accelerations = vec![Vec2::zero(); particles.len()];
+        params.accumulate(&particles, &grid, &mut accelerations);
+
+        for (i, a) in particles.iter().enumerate() {
+            let expected = particles
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(Vec2::zero(), |total, (_, b)| {
+                    total + params.force(a.charge, b.charge, a.position - b.position, a.radius + b.radius) * a.effective_inv_mass()
+                });
+            assert!((accelerations[i] - expected).length() < 1e-3, "Particle {}", i);
+        }
+    }
+}
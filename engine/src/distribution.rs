@@ -0,0 +1,85 @@
+use crate::rng::Rng;
+
+/// Smallest radius any particle may have
+pub const MIN_RADIUS: f32 = 0.5;
+
+/// Largest radius any particle may have
+pub const MAX_RADIUS: f32 = 500.0;
+
+/// Radius given to particles spawned without an explicit size
+pub const DEFAULT_RADIUS: f32 = 4.0;
+
+/// Size distribution sampled when new particles are spawned
+#[derive(Clone, Debug, PartialEq)]
+pub enum RadiusDistribution {
+    /// Every particle gets the same radius
+    Fixed(f32),
+    /// Uniform between min and max
+    Uniform { min: f32, max: f32 },
+    /// Normal around the mean, truncated to three standard deviations
+    Normal { mean: f32, std_dev: f32 },
+    /// Uniform pick from a list of radii
+    Discrete(Vec<f32>),
+}
+
+impl Default for RadiusDistribution {
+    fn default() -> Self {
+        RadiusDistribution::Fixed(DEFAULT_RADIUS)
+    }
+}
+
+impl RadiusDistribution {
+    /// Draw a radius, always inside [MIN_RADIUS, MAX_RADIUS]
+    pub fn sample(&self, rng: &mut Rng) -> f32 {
+        let radius = match self {
+            RadiusDistribution::Fixed(radius) => *radius,
+            RadiusDistribution::Uniform { min, max } => rng.range(*min, *max),
+            RadiusDistribution::Normal { mean, std_dev } => {
+                mean + std_dev * rng.normal().clamp(-3.0, 3.0)
+            }
+            RadiusDistribution::Discrete(values) => {
+                values[rng.next_u32() as usize % values.len()]
+            }
+        };
+
+        radius.clamp(MIN_RADIUS, MAX_RADIUS)
+    }
+}
+
+/// Check that a radius is finite and inside [MIN_RADIUS, MAX_RADIUS]
+pub fn validate_radius(radius: f32) -> Result<f32, String> {
+    crate::config::validate_range("radius", radius, MIN_RADIUS, MAX_RADIUS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_stay_within_bounds() {
+        let mut rng = Rng::new(1);
+        let distributions = [
+            RadiusDistribution::Fixed(3.0),
+            RadiusDistribution::Uniform { min: 2.0, max: 6.0 },
+            RadiusDistribution::Normal { mean: 4.0, std_dev: 1.0 },
+            RadiusDistribution::Discrete(vec![2.0, 8.0]),
+        ];
+
+        for distribution in &distributions {
+            for _ in 0..1000 {
+                let radius = distribution.sample(&mut rng);
+                assert!((1.0..=8.0).contains(&radius), "{:?} produced {}", distribution, radius);
+            }
+        }
+    }
+
+    #[test]
+    fn test_discrete_picks_listed_values() {
+        let mut rng = Rng::new(2);
+        let distribution = RadiusDistribution::Discrete(vec![2.0, 8.0]);
+        let samples: Vec<f32> = (0..100).map(|_| distribution.sample(&mut rng)).collect();
+
+        assert!(samples.iter().all(|&r| r == 2.0 || r == 8.0));
+        assert!(samples.contains(&2.0) && samples.contains(&8.0));
+    }
+}
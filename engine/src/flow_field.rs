@@ -0,0 +1,279 @@
+use crate::config::validate_range;
+use crate::rng::Rng;
+use crate::Vec2;
+
+/// Step used for the finite-difference curl, in noise-space units
+const CURL_EPSILON: f32 = 1e-3;
+
+/// Largest accepted flow response, keeps the explicit drag step stable at 60 FPS
+pub const MAX_FLOW_RESPONSE: f32 = 50.0;
+
+/// Default rate (per second) at which particles adopt the local flow velocity
+pub const DEFAULT_FLOW_RESPONSE: f32 = 2.0;
+
+/// Most nodes along either side of a user-supplied flow grid
+pub const MAX_FLOW_GRID_SIDE: usize = 1024;
+
+/// Seeded 3D gradient noise (improved Perlin noise)
+#[derive(Clone, Debug)]
+pub struct PerlinNoise {
+    // Permutation table, duplicated so lookups never need wrapping
+    permutation: Vec<u8>,
+}
+
+impl PerlinNoise {
+    /// Build the permutation table from a seed
+    pub fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        let mut rng = Rng::new(seed);
+
+        // Fisher-Yates shuffle
+        for i in (1..table.len()).rev() {
+            let j = rng.next_u32() as usize % (i + 1);
+            table.swap(i, j);
+        }
+
+        let permutation = table.iter().chain(table.iter()).copied().collect();
+        PerlinNoise { permutation }
+    }
+
+    /// Noise value at (x, y, z), roughly in [-1, 1]
+    pub fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, xf) = Self::split(x);
+        let (yi, yf) = Self::split(y);
+        let (zi, zf) = Self::split(z);
+
+        let u = Self::fade(xf);
+        let v = Self::fade(yf);
+        let w = Self::fade(zf);
+
+        let p = &self.permutation;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+
+        let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, Self::grad(p[aa], xf, yf, zf), Self::grad(p[ba], xf - 1.0, yf, zf)),
+                lerp(u, Self::grad(p[ab], xf, yf - 1.0, zf), Self::grad(p[bb], xf - 1.0, yf - 1.0, zf)),
+            ),
+            lerp(
+                v,
+                lerp(u, Self::grad(p[aa + 1], xf, yf, zf - 1.0), Self::grad(p[ba + 1], xf - 1.0, yf, zf - 1.0)),
+                lerp(
+                    u,
+                    Self::grad(p[ab + 1], xf, yf - 1.0, zf - 1.0),
+                    Self::grad(p[bb + 1], xf - 1.0, yf - 1.0, zf - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Lattice cell (wrapped to the table size) and fractional offset inside it
+    fn split(value: f32) -> (usize, f32) {
+        let floor = value.floor();
+        ((floor as i64).rem_euclid(256) as usize, value - floor)
+    }
+
+    /// Quintic smoothstep 6t⁵ - 15t⁴ + 10t³
+    fn fade(t: f32) -> f32 {
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    }
+
+    /// Dot product with one of 12 edge gradients picked by the hash
+    fn grad(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+        let h = hash & 15;
+        let u = if h < 8 { x } else { y };
+        let v = if h < 4 {
+            y
+        } else if h == 12 || h == 14 {
+            x
+        } else {
+            z
+        };
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    }
+}
+
+/// Where the flow velocity comes from
+#[derive(Clone, Debug)]
+pub enum FlowSource {
+    /// Curl of animated noise: divergence-free by construction
+    CurlNoise {
+        noise: PerlinNoise,
+        // Noise features per unit of distance
+        scale: f32,
+        // Noise-space drift per second along the time axis
+        speed: f32,
+        // Flow speed multiplier in units per second
+        strength: f32,
+    },
+    /// Velocities on a regular grid of nodes spanning the container, [vx, vy] row-major
+    Grid { cols: usize, rows: usize, velocities: Vec<Vec2> },
+}
+
+/// Velocity field that particles are dragged along
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    pub source: FlowSource,
+    // Rate (per second) at which particle velocity relaxes towards the flow
+    pub response: f32,
+    // Elapsed simulation time driving the noise animation
+    pub time: f32,
+}
+
+impl FlowField {
+    /// Create a curl-noise flow
+    pub fn curl_noise(seed: u64, scale: f32, speed: f32, strength: f32) -> Result<FlowField, String> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(format!("Flow scale must be positive, got {}", scale));
+        }
+        if !(speed.is_finite() && strength.is_finite()) {
+            return Err(format!("Flow speed and strength must be finite, got {} and {}", speed, strength));
+        }
+
+        Ok(FlowField {
+            source: FlowSource::CurlNoise { noise: PerlinNoise::new(seed), scale, speed, strength },
+            response: DEFAULT_FLOW_RESPONSE,
+            time: 0.0,
+        })
+    }
+
+    /// Create a flow from a user-supplied grid of velocities ([vx, vy] per node, row-major)
+    pub fn grid(cols: usize, rows: usize, data: &[f32]) -> Result<FlowField, String> {
+        validate_range("flow grid columns", cols as f32, 2.0, MAX_FLOW_GRID_SIDE as f32)?;
+        validate_range("flow grid rows", rows as f32, 2.0, MAX_FLOW_GRID_SIDE as f32)?;
+        let expected = cols
+            .checked_mul(rows)
+            .and_then(|nodes| nodes.checked_mul(2))
+            .ok_or_else(|| format!("Flow grid of {}x{} is too large", cols, rows))?;
+        if data.len() != expected {
+            return Err(format!("Flow grid of {}x{} needs {} values, got {}", cols, rows, expected, data.len()));
+        }
+        if data.iter().any(|value| !value.is_finite()) {
+            return Err("Flow grid values must be finite".to_string());
+        }
+
+        let velocities = data.chunks_exact(2).map(|v| Vec2::new(v[0], v[1])).collect();
+        Ok(FlowField { source: FlowSource::Grid { cols, rows, velocities }, response: DEFAULT_FLOW_RESPONSE, time: 0.0 })
+    }
+
+    /// Flow velocity at a position inside a container of the given size
+    pub fn velocity_at(&self, position: Vec2, width: f32, height: f32) -> Vec2 {
+        match &self.source {
+            FlowSource::CurlNoise { noise, scale, speed, strength } => {
+                let x = position.x * scale;
+                let y = position.y * scale;
+                let z = self.time * speed;
+
+                // Velocity is the curl of the scalar potential: (dψ/dy, -dψ/dx)
+                let dpsi_dx = (noise.sample(x + CURL_EPSILON, y, z) - noise.sample(x - CURL_EPSILON, y, z))
+                    / (2.0 * CURL_EPSILON);
+                let dpsi_dy = (noise.sample(x, y + CURL_EPSILON, z) - noise.sample(x, y - CURL_EPSILON, z))
+                    / (2.0 * CURL_EPSILON);
+                Vec2::new(dpsi_dy, -dpsi_dx) * *strength
+            }
+            FlowSource::Grid { cols, rows, velocities } => {
+                // Bilinear interpolation between nodes, clamped at the container edges
+                let gx = (position.x / width.max(f32::EPSILON) * (*cols - 1) as f32).clamp(0.0, (*cols - 1) as f32);
+                let gy = (position.y / height.max(f32::EPSILON) * (*rows - 1) as f32).clamp(0.0, (*rows - 1) as f32);
+                let x0 = (gx.floor() as usize).min(cols - 2);
+                let y0 = (gy.floor() as usize).min(rows - 2);
+                let tx = gx - x0 as f32;
+                let ty = gy - y0 as f32;
+
+                let node = |x: usize, y: usize| velocities[y * cols + x];
+                let top = node(x0, y0) * (1.0 - tx) + node(x0 + 1, y0) * tx;
+                let bottom = node(x0, y0 + 1) * (1.0 - tx) + node(x0 + 1, y0 + 1) * tx;
+                top * (1.0 - ty) + bottom * ty
+            }
+        }
+    }
+
+    /// Acceleration pulling a particle moving at `velocity` towards the local flow
+    pub fn acceleration_at(&self, position: Vec2, velocity: Vec2, width: f32, height: f32) -> Vec2 {
+        (self.velocity_at(position, width, height) - velocity) * self.response
+    }
+
+    /// Advance the noise animation
+    pub fn advance(&mut self, dt: f32) {
+        self.time += dt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_is_seeded_and_smooth() {
+        let a = PerlinNoise::new(1);
+        let b = PerlinNoise::new(1);
+        let c = PerlinNoise::new(2);
+
+        assert_eq!(a.sample(1.3, 2.7, 0.5), b.sample(1.3, 2.7, 0.5));
+        assert_ne!(a.sample(1.3, 2.7, 0.5), c.sample(1.3, 2.7, 0.5));
+
+        // Zero on lattice points, small steps give small changes
+        assert_eq!(a.sample(3.0, 4.0, 5.0), 0.0);
+        assert!((a.sample(1.3, 2.7, 0.5) - a.sample(1.301, 2.7, 0.5)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_curl_noise_is_divergence_free() {
+        let field = FlowField::curl_noise(7, 0.01, 0.5, 40.0).unwrap();
+        let h = 0.5;
+        let mut divergence_total = 0.0;
+        let mut speed_total = 0.0;
+
+        for i in 0..100 {
+            let p = Vec2::new(13.0 + i as f32 * 7.3, 29.0 + i as f32 * 4.1);
+            let dvx_dx = (field.velocity_at(p + Vec2::new(h, 0.0), 0.0, 0.0).x
+                - field.velocity_at(p - Vec2::new(h, 0.0), 0.0, 0.0).x) / (2.0 * h);
+            let dvy_dy = (field.velocity_at(p + Vec2::new(0.0, h), 0.0, 0.0).y
+                - field.velocity_at(p - Vec2::new(0.0, h), 0.0, 0.0).y) / (2.0 * h);
+            divergence_total += (dvx_dx + dvy_dy).abs();
+            speed_total += field.velocity_at(p, 0.0, 0.0).length();
+        }
+
+        // Divergence (1/s) should be tiny compared to the velocity gradient scale (speed * noise scale)
+        let mean_divergence = divergence_total / 100.0;
+        let mean_gradient_scale = speed_total / 100.0 * 0.01;
+        assert!(speed_total > 0.0);
+        assert!(mean_divergence < mean_gradient_scale * 0.05,
+                "Divergence {} vs gradient scale {}", mean_divergence, mean_gradient_scale);
+    }
+
+    #[test]
+    fn test_curl_noise_evolves_over_time() {
+        let mut field = FlowField::curl_noise(3, 0.02, 1.0, 10.0).unwrap();
+        let p = Vec2::new(120.0, 80.0);
+        let before = field.velocity_at(p, 0.0, 0.0);
+        field.advance(0.5);
+        assert_ne!(field.velocity_at(p, 0.0, 0.0), before);
+    }
+
+    #[test]
+    fn test_grid_bilinear_sampling() {
+        // 2x2 grid: left column moves right, right column moves down
+        let data = [10.0, 0.0, 0.0, 10.0, 10.0, 0.0, 0.0, 10.0];
+        let field = FlowField::grid(2, 2, &data).unwrap();
+
+        assert_eq!(field.velocity_at(Vec2::new(0.0, 0.0), 100.0, 100.0), Vec2::new(10.0, 0.0));
+        assert_eq!(field.velocity_at(Vec2::new(100.0, 50.0), 100.0, 100.0), Vec2::new(0.0, 10.0));
+        assert_eq!(field.velocity_at(Vec2::new(50.0, 50.0), 100.0, 100.0), Vec2::new(5.0, 5.0));
+        assert_eq!(field.velocity_at(Vec2::new(-50.0, 500.0), 100.0, 100.0), Vec2::new(10.0, 0.0));
+
+        assert!(FlowField::grid(2, 2, &data[..6]).is_err());
+        assert!(FlowField::grid(1, 4, &data).is_err());
+        assert!(FlowField::grid(65536, 32768, &[]).is_err());
+        assert!(FlowField::grid(usize::MAX, usize::MAX, &[]).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Vec2;
+
+/// Radius, as a fraction of the field radius, where the inverse-square falloff halves
+const INVERSE_SQUARE_CORE: f32 = 0.1;
+
+/// Standard deviation of the Gaussian falloff, as a fraction of the field radius
+const GAUSSIAN_SIGMA: f32 = 1.0 / 3.0;
+
+/// Shape of the force a field applies around its centre
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForceFieldKind {
+    /// Pull towards the centre
+    Attract,
+    /// Push away from the centre
+    Repel,
+    /// Swirl around the centre (clockwise on screen for positive strength)
+    Vortex,
+    /// Push along a fixed direction
+    Directional,
+    /// Pull towards the centre in proportion to distance, contracting the whole area
+    Implode,
+}
+
+/// How field strength decays from the centre (t = 0) to the radius (t = 1)
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    /// 1 - t
+    Linear,
+    /// (1 - t)²
+    Quadratic,
+    /// Softened 1 / t², halved at a tenth of the radius
+    InverseSquare,
+    /// Bell curve with σ at a third of the radius
+    Gaussian,
+}
+
+impl Falloff {
+    /// Weight in [0, 1] at normalised distance `t` inside the field
+    pub fn weight(self, t: f32) -> f32 {
+        match self {
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => (1.0 - t) * (1.0 - t),
+            Falloff::InverseSquare => {
+                let core = INVERSE_SQUARE_CORE * INVERSE_SQUARE_CORE;
+                core / (core + t * t)
+            }
+            Falloff::Gaussian => (-(t * t) / (2.0 * GAUSSIAN_SIGMA * GAUSSIAN_SIGMA)).exp(),
+        }
+    }
+}
+
+/// A circular region that accelerates particles inside it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForceField {
+    pub kind: ForceFieldKind,
+    pub center: Vec2,
+    pub radius: f32,
+    // Peak acceleration in units per second squared
+    pub strength: f32,
+    pub falloff: Falloff,
+    // Unit push direction, only used by `Directional`
+    pub direction: Vec2,
+}
+
+impl ForceField {
+    /// Build a field, validating its parameters
+    pub fn new(
+        kind: ForceFieldKind,
+        center: Vec2,
+        radius: f32,
+        strength: f32,
+        falloff: Falloff,
+        direction: Vec2,
+    ) -> Result<ForceField, String> {
+        if !(center.x.is_finite() && center.y.is_finite()) {
+            return Err(format!("Force field centre must be finite, got ({}, {})", center.x, center.y));
+        }
+        if !(radius.is_finite() && radius > 0.0) {
+            return Err(format!("Force field radius must be positive, got {}", radius));
+        }
+        if !strength.is_finite() {
+            return Err(format!("Force field strength must be finite, got {}", strength));
+        }
+
+        let direction = if kind == ForceFieldKind::Directional {
+            let length = direction.length();
+            if !(length.is_finite() && length > 0.0) {
+                return Err("Directional force fields need a non-zero direction".to_string());
+            }
+            direction * (1.0 / length)
+        } else {
+            Vec2::zero()
+        };
+
+        Ok(ForceField { kind, center, radius, strength, falloff, direction })
+    }
+
+    /// Acceleration this field applies at `position` (zero outside the radius)
+    pub fn acceleration_at(&self, position: Vec2) -> Vec2 {
+        let diff = position - self.center;
+        let distance = diff.length();
+        if distance >= self.radius {
+            return Vec2::zero();
+        }
+
+        let t = distance / self.radius;
+        let magnitude = self.strength * self.falloff.weight(t);
+
+        if self.kind == ForceFieldKind::Directional {
+            return self.direction * magnitude;
+        }
+
+        // Radial kinds have no defined direction exactly at the centre
+        if distance <= 0.0 {
+            return Vec2::zero();
+        }
+        let outward = diff * (1.0 / distance);
+
+        match self.kind {
+            ForceFieldKind::Repel => outward * magnitude,
+            ForceFieldKind::Attract => outward * -magnitude,
+            ForceFieldKind::Vortex => Vec2::new(-outward.y, outward.x) * magnitude,
+            ForceFieldKind::Implode => outward * -(magnitude * t),
+            ForceFieldKind::Directional => unreachable!(),
+        }
+    }
+}
+
+/// Persistent force fields addressed by ID, applied on every simulation step
+#[derive(Clone, Debug, Default)]
+pub struct ForceEmitters {
+    emitters: Vec<(u32, ForceField)>,
+    next_id: u32,
+}
+
+impl ForceEmitters {
+    /// Store a field and return its ID
+    pub fn add(&mut self, field: ForceField) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.emitters.push((id, field));
+        id
+    }
+
+    /// Look up a field by ID
+    pub fn get(&self, id: u32) -> Result<&ForceField, String> {
+        self.emitters
+            .iter()
+            .find(|(emitter_id, _)| *emitter_id == id)
+            .map(|(_, field)| field)
+            .ok_or_else(|| format!("Unknown force emitter {}", id))
+    }
+
+    /// Replace the field stored under an ID
+    pub fn replace(&mut self, id: u32, field: ForceField) -> Result<(), String> {
+        let slot = self
+            .emitters
+            .iter_mut()
+            .find(|(emitter_id, _)| *emitter_id == id)
+            .ok_or_else(|| format!("Unknown force emitter {}", id))?;
+        slot.1 = field;
+        Ok(())
+    }
+
+    /// Remove a field; returns false if the ID is unknown
+    pub fn remove(&mut self, id: u32) -> bool {
+        let count = self.emitters.len();
+        self.emitters.retain(|(emitter_id, _)| *emitter_id != id);
+        self.emitters.len() != count
+    }
+
+    /// Remove every field
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+    }
+
+    /// Number of stored fields
+    pub fn len(&self) -> usize {
+        self.emitters.len()
+    }
+
+    /// Whether no fields are stored
+    pub fn is_empty(&self) -> bool {
+        self.emitters.is_empty()
+    }
+
+    /// Sum of all field accelerations at `position`
+    pub fn acceleration_at(&self, position: Vec2) -> Vec2 {
+        self.emitters
+            .iter()
+            .fold(Vec2::zero(), |total, (_, field)| total + field.acceleration_at(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(kind: ForceFieldKind) -> ForceField {
+        ForceField::new(kind, Vec2::zero(), 100.0, 10.0, Falloff::Linear, Vec2::new(0.0, 2.0)).unwrap()
+    }
+
+    #[test]
+    fn test_falloff_curves() {
+        for falloff in [Falloff::Linear, Falloff::Quadratic, Falloff::InverseSquare, Falloff::Gaussian] {
+            assert_eq!(falloff.weight(0.0), 1.0);
+            assert!(falloff.weight(0.5) < 1.0 && falloff.weight(0.5) > falloff.weight(0.9));
+            assert!(falloff.weight(1.0) < 0.02, "{:?} at the rim: {}", falloff, falloff.weight(1.0));
+        }
+
+        assert_eq!(Falloff::Linear.weight(0.5), 0.5);
+        assert_eq!(Falloff::Quadratic.weight(0.5), 0.25);
+        assert!((Falloff::InverseSquare.weight(INVERSE_SQUARE_CORE) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_field_directions() {
+        let position = Vec2::new(50.0, 0.0);
+
+        assert_eq!(field(ForceFieldKind::Repel).acceleration_at(position), Vec2::new(5.0, 0.0));
+        assert_eq!(field(ForceFieldKind::Attract).acceleration_at(position), Vec2::new(-5.0, 0.0));
+        assert_eq!(field(ForceFieldKind::Vortex).acceleration_at(position), Vec2::new(0.0, 5.0));
+        assert_eq!(field(ForceFieldKind::Directional).acceleration_at(position), Vec2::new(0.0, 5.0));
+        assert_eq!(field(ForceFieldKind::Implode).acceleration_at(position), Vec2::new(-2.5, 0.0));
+
+        // Nothing happens outside the radius
+        for kind in [ForceFieldKind::Repel, ForceFieldKind::Directional] {
+            assert_eq!(field(kind).acceleration_at(Vec2::new(150.0, 0.0)), Vec2::zero());
+        }
+    }
+
+    #[test]
+    fn test_invalid_fields_are_rejected() {
+        let center = Vec2::zero();
+        assert!(ForceField::new(ForceFieldKind::Repel, center, 0.0, 1.0, Falloff::Linear, center).is_err());
+        assert!(ForceField::new(ForceFieldKind::Repel, center, 10.0, f32::NAN, Falloff::Linear, center).is_err());
+        assert!(ForceField::new(ForceFieldKind::Directional, center, 10.0, 1.0, Falloff::Linear, center).is_err());
+    }
+
+    #[test]
+    fn test_emitters_sum_and_remove() {
+        let mut emitters = ForceEmitters::default();
+        let repel = emitters.add(field(ForceFieldKind::Repel));
+        let attract = emitters.add(field(ForceFieldKind::Attract));
+        assert_ne!(repel, attract);
+        assert_eq!(emitters.len(), 2);
+
+        // Opposite fields cancel out
+        assert_eq!(emitters.acceleration_at(Vec2::new(50.0, 0.0)), Vec2::zero());
+
+        assert!(emitters.remove(attract));
+        assert!(!emitters.remove(attract));
+        assert_eq!(emitters.acceleration_at(Vec2::new(50.0, 0.0)), Vec2::new(5.0, 0.0));
+
+        assert!(emitters.replace(attract, field(ForceFieldKind::Vortex)).is_err());
+        assert!(emitters.get(repel).is_ok());
+    }
+}
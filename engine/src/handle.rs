@@ -0,0 +1,45 @@
+/// Bits of a particle handle used for the slot index; the rest hold the generation
+const INDEX_BITS: u32 = 20;
+
+/// Mask selecting the slot index from a handle
+const INDEX_MASK: u32 = (1 << INDEX_BITS) - 1;
+
+/// Number of distinct generations a slot cycles through before wrapping
+const GENERATION_MASK: u32 = (1 << (32 - INDEX_BITS)) - 1;
+
+/// Largest number of particle slots addressable by a handle
+pub const MAX_SLOTS: usize = 1 << INDEX_BITS;
+
+/// Pack a slot index and generation into a JS-friendly `u32` handle
+pub fn pack(index: usize, generation: u32) -> u32 {
+    debug_assert!(index < MAX_SLOTS);
+    ((generation & GENERATION_MASK) << INDEX_BITS) | index as u32
+}
+
+/// Split a handle into its slot index and generation
+pub fn unpack(handle: u32) -> (usize, u32) {
+    ((handle & INDEX_MASK) as usize, handle >> INDEX_BITS)
+}
+
+/// Generation a slot moves to when its particle is removed
+pub fn next_generation(generation: u32) -> u32 {
+    generation.wrapping_add(1) & GENERATION_MASK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_round_trip() {
+        let handle = pack(12345, 7);
+        assert_eq!(unpack(handle), (12345, 7));
+        assert_ne!(pack(12345, 8), handle);
+    }
+
+    #[test]
+    fn test_generation_wraps() {
+        assert_eq!(next_generation(GENERATION_MASK), 0);
+        assert_eq!(unpack(pack(MAX_SLOTS - 1, GENERATION_MASK)), (MAX_SLOTS - 1, GENERATION_MASK));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::config::validate_range;
+use crate::Vec2;
+
+/// Upper bound on registered materials, so a runaway loop in JS cannot grow the table forever
+pub const MAX_MATERIALS: usize = 256;
+
+/// Largest cohesion or surface tension accepted, in units per second squared
+const MAX_STRENGTH: f32 = 1e6;
+
+/// Largest friction coefficient accepted
+pub const MAX_FRICTION: f32 = 10.0;
+
+/// Surface properties shared by every particle that references the material
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Material {
+    // Peak attraction (acceleration for unit mass) between particles just outside contact
+    pub cohesion: f32,
+    // Strength of the curvature force that pulls surface particles inward, beading droplets
+    pub surface_tension: f32,
+    // Coulomb friction: contacts stick while tangential slip stays below static × penetration,
+    // otherwise slip is reduced by kinetic × penetration
+    pub static_friction: f32,
+    pub kinetic_friction: f32,
+    // Fraction of the penetration depth removed from a touching particle's tangential speed
+    // every step, standing in for the torque that stops real grains from rolling
+    pub rolling_resistance: f32,
+}
+
+impl Material {
+    /// Build a material, validating its parameters
+    pub fn new(cohesion: f32, surface_tension: f32) -> Result<Material, String> {
+        Ok(Material {
+            cohesion: validate_range("cohesion", cohesion, 0.0, MAX_STRENGTH)?,
+            surface_tension: validate_range("surface_tension", surface_tension, 0.0, MAX_STRENGTH)?,
+            ..Material::default()
+        })
+    }
+
+    /// Copy of this material with new friction coefficients, validated
+    pub fn with_friction(self, static_friction: f32, kinetic_friction: f32, rolling_resistance: f32) -> Result<Material, String> {
+        Ok(Material {
+            static_friction: validate_range("static_friction", static_friction, 0.0, MAX_FRICTION)?,
+            kinetic_friction: validate_range("kinetic_friction", kinetic_friction, 0.0, MAX_FRICTION)?,
+            rolling_resistance: validate_range("rolling_resistance", rolling_resistance, 0.0, 1.0)?,
+            ..self
+        })
+    }
+
+    /// Position correction that friction removes from a contact's tangential slip this step.
+    /// `depth` is the penetration being resolved, standing in for the normal force.
+    pub fn friction_correction(&self, tangential_slip: Vec2, depth: f32) -> Vec2 {
+        let slip = tangential_slip.length();
+        if slip <= 0.0 || depth <= 0.0 {
+            return Vec2::zero();
+        }
+
+        if slip < self.static_friction * depth {
+            tangential_slip // Sticking: cancel all slip
+        } else {
+            tangential_slip * (self.kinetic_friction * depth / slip).min(1.0)
+        }
+    }
+
+    /// Tangential velocity (displacement per step) a touching particle keeps after rolling resistance
+    pub fn roll(&self, tangential_velocity: Vec2, depth: f32) -> Vec2 {
+        let speed = tangential_velocity.length();
+        if speed <= 0.0 || self.rolling_resistance <= 0.0 {
+            return tangential_velocity;
+        }
+        tangential_velocity * ((speed - self.rolling_resistance * depth).max(0.0) / speed)
+    }
+
+    /// Whether contacts involving this material resist sliding or rolling
+    pub fn has_friction(&self) -> bool {
+        self.static_friction > 0.0 || self.kinetic_friction > 0.0 || self.rolling_resistance > 0.0
+    }
+
+    /// Whether particles of this material attract their neighbours at all
+    pub fn is_cohesive(&self) -> bool {
+        self.cohesion > 0.0 || self.surface_tension > 0.0
+    }
+}
+
+/// Material table indexed by `Particle::material`; entry 0 is the inert default material
+#[derive(Clone, Debug)]
+pub struct Materials {
+    materials: Vec<Material>,
+}
+
+impl Default for Materials {
+    fn default() -> Self {
+        Materials { materials: vec![Material::default()] }
+    }
+}
+
+impl Materials {
+    /// Register a material and return its ID
+    pub fn add(&mut self, material: Material) -> Result<u32, String> {
+        if self.materials.len() >= MAX_MATERIALS {
+            return Err(format!("Material limit of {} reached", MAX_MATERIALS));
+        }
+        self.materials.push(material);
+        Ok(self.materials.len() as u32 - 1)
+    }
+
+    /// Look up a material by ID
+    pub fn get(&self, id: u32) -> Result<&Material, String> {
+        self.materials.get(id as usize).ok_or_else(|| format!("Unknown material {}", id))
+    }
+
+    /// Replace the material stored under an ID
+    pub fn replace(&mut self, id: u32, material: Material) -> Result<(), String> {
+        let slot = self.materials.get_mut(id as usize).ok_or_else(|| format!("Unknown material {}", id))?;
+        *slot = material;
+        Ok(())
+    }
+
+    /// Number of registered materials, including the default one
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    /// Whether any material has friction or rolling resistance
+    pub fn any_friction(&self) -> bool {
+        self.materials.iter().any(Material::has_friction)
+    }
+
+    /// Whether any material makes particles attract each other
+    pub fn any_cohesive(&self) -> bool {
+        self.materials.iter().any(Material::is_cohesive)
+    }
+
+    /// Combined properties of a pair of materials. Attraction uses the geometric mean of
+    /// both sides, so nothing sticks to an inert material; friction uses the plain mean.
+    pub fn pair(&self, a: u32, b: u32) -> Material {
+        let (a, b) = (self.materials[a as usize], self.materials[b as usize]);
+        Material {
+            cohesion: (a.cohesion * b.cohesion).sqrt(),
+            surface_tension: (a.surface_tension * b.surface_tension).sqrt(),
+            static_friction: (a.static_friction + b.static_friction) * 0.5,
+            kinetic_friction: (a.kinetic_friction + b.kinetic_friction) * 0.5,
+            rolling_resistance: (a.rolling_resistance + b.rolling_resistance) * 0.5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_table() {
+        let mut materials = Materials::default();
+        assert_eq!(materials.len(), 1);
+        assert!(!materials.any_cohesive());
+
+        let clay = materials.add(Material::new(400.0, 0.0).unwrap()).unwrap();
+        assert_eq!(clay, 1);
+        assert!(materials.any_cohesive());
+        assert_eq!(materials.pair(0, clay).cohesion, 0.0);
+        assert_eq!(materials.pair(clay, clay).cohesion, 400.0);
+        let mud = materials.add(Material::new(100.0, 0.0).unwrap()).unwrap();
+        assert_eq!(materials.pair(clay, mud).cohesion, 200.0);
+
+        assert!(Material::new(-1.0, 0.0).is_err());
+        assert!(Material::new(0.0, f32::NAN).is_err());
+        assert!(materials.get(7).is_err());
+        assert!(materials.replace(7, Material::default()).is_err());
+    }
+
+    #[test]
+    fn test_friction_sticks_then_slides() {
+        let sand = Material::default().with_friction(0.8, 0.4, 0.0).unwrap();
+        let depth = 1.0;
+
+        // Small slip is cancelled entirely
+        let slip = Vec2::new(0.5, 0.0);
+        assert_eq!(sand.friction_correction(slip, depth), slip);
+
+        // Large slip is only reduced by the kinetic share
+        let slip = Vec2::new(2.0, 0.0);
+        assert!((sand.friction_correction(slip, depth).x - 0.4).abs() < 1e-6);
+
+        // No contact, no friction
+        assert_eq!(sand.friction_correction(slip, 0.0), Vec2::zero());
+        assert_eq!(Material::default().friction_correction(slip, depth), Vec2::zero());
+
+        let rolling = Material::default().with_friction(0.0, 0.0, 0.5).unwrap();
+        assert_eq!(rolling.roll(Vec2::new(2.0, 0.0), 1.0), Vec2::new(1.5, 0.0));
+        assert_eq!(rolling.roll(Vec2::new(0.2, 0.0), 1.0), Vec2::zero());
+
+        assert!(sand.with_friction(-0.1, 0.0, 0.0).is_err());
+        assert!(sand.with_friction(0.5, 0.5, 2.0).is_err());
+    }
+}
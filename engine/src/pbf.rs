@@ -0,0 +1,289 @@
+use crate::spatial_grid::SpatialGrid;
+use crate::sph::SphParams;
+use crate::{Particle, Vec2};
+
+/// Distance, as a fraction of the smoothing radius, where the tensile correction is referenced
+const TENSILE_REFERENCE: f32 = 0.2;
+
+/// Exponent of the tensile correction term
+const TENSILE_EXPONENT: i32 = 4;
+
+/// Tunable parameters of the Position Based Fluids solver (Macklin & Müller 2013).
+/// Rest density and smoothing radius are shared with the SPH mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PbfParams {
+    // Constraint force mixing ε added to the λ denominator; larger is softer but more stable
+    pub relaxation: f32,
+    // Strength k of the artificial pressure that prevents particle clumping (tensile instability)
+    pub tensile_strength: f32,
+    // XSPH viscosity c in [0, 1]: how far each particle blends towards its neighbours' velocity
+    pub xsph: f32,
+    // Vorticity confinement ε in units per second, re-injects swirl lost to damping
+    pub vorticity: f32,
+}
+
+impl Default for PbfParams {
+    fn default() -> Self {
+        PbfParams {
+            relaxation: 1e-4,
+            tensile_strength: 0.1,
+            xsph: 0.05,
+            vorticity: 10.0,
+        }
+    }
+}
+
+/// Position Based Fluids state: parameters, neighbour pairs and per-particle scratch
+#[derive(Default)]
+pub struct Pbf {
+    pub params: PbfParams,
+    grid: SpatialGrid,
+    // Fluid particle pairs in neighbouring grid cells, collected once per step
+    pairs: Vec<(usize, usize)>,
+    lambdas: Vec<f32>,
+    // Σ ∇C per particle and Σ |∇C|² over its neighbours, for the λ denominator
+    gradient_sums: Vec<Vec2>,
+    gradient_norms: Vec<f32>,
+    corrections: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    velocity_changes: Vec<Vec2>,
+    vorticities: Vec<f32>,
+    vorticity_gradients: Vec<Vec2>,
+}
+
+impl Pbf {
+    /// Collect neighbour candidates around the predicted positions; call once per step
+    pub fn find_neighbours(&mut self, particles: &[Particle], kernel: &SphParams, width: f32, height: f32) {
+        self.grid.update(particles, kernel.smoothing_radius, width, height);
+
+        let pairs = &mut self.pairs;
+        pairs.clear();
+        self.grid.for_each_candidate_pair(|i, j| {
+            if Self::is_fluid(&particles[i]) && Self::is_fluid(&particles[j]) {
+                pairs.push((i, j));
+            }
+        });
+    }
+
+    /// One Jacobi pass over the density constraints C = ρ/ρ₀ - 1 ≤ 0, moving particles
+    /// so no neighbourhood is compressed. Writes the densities it measured to `densities`.
+    pub fn solve_density(&mut self, particles: &mut [Particle], kernel: &SphParams, densities: &mut [f32]) {
+        let count = particles.len();
+        let rest_density = kernel.rest_density;
+        Self::reset(&mut self.lambdas, count, 0.0);
+        Self::reset(&mut self.gradient_sums, count, Vec2::zero());
+        Self::reset(&mut self.gradient_norms, count, 0.0);
+        Self::reset(&mut self.corrections, count, Vec2::zero());
+
+        for (density, particle) in densities.iter_mut().zip(particles.iter()) {
+            *density = if Self::is_fluid(particle) { particle.mass() * kernel.poly6(0.0) } else { 0.0 };
+        }
+
+        for &(i, j) in &self.pairs {
+            let diff = particles[i].position - particles[j].position;
+            let distance_sq = diff.x * diff.x + diff.y * diff.y;
+            densities[i] += particles[j].mass() * kernel.poly6(distance_sq);
+            densities[j] += particles[i].mass() * kernel.poly6(distance_sq);
+
+            let gradient = Self::kernel_gradient(kernel, diff);
+            let gradient_i = gradient * (particles[j].mass() / rest_density);
+            let gradient_j = gradient * (particles[i].mass() / rest_density);
+            self.gradient_sums[i] = self.gradient_sums[i] + gradient_i;
+            self.gradient_sums[j] = self.gradient_sums[j] - gradient_j;
+            self.gradient_norms[i] += gradient_i.x * gradient_i.x + gradient_i.y * gradient_i.y;
+            self.gradient_norms[j] += gradient_j.x * gradient_j.x + gradient_j.y * gradient_j.y;
+        }
+
+        for (i, &density) in densities.iter().enumerate() {
+            if density <= 0.0 {
+                continue;
+            }
+            // Only compression is resolved, so the free surface does not pull itself together
+            let constraint = (density / rest_density - 1.0).max(0.0);
+            let sum = self.gradient_sums[i];
+            let denominator = sum.x * sum.x + sum.y * sum.y + self.gradient_norms[i] + self.params.relaxation;
+            self.lambdas[i] = -constraint / denominator;
+        }
+
+        // Artificial pressure s_corr = -k h² (W(r) / W(Δq))ⁿ keeps particles from clumping
+        let h = kernel.smoothing_radius;
+        let reference = TENSILE_REFERENCE * h;
+        let reference_weight = kernel.poly6(reference * reference);
+        let tensile_scale = self.params.tensile_strength * h * h;
+
+        for &(i, j) in &self.pairs {
+            let diff = particles[i].position - particles[j].position;
+            let weight = kernel.poly6(diff.x * diff.x + diff.y * diff.y);
+            let tensile = -tensile_scale * (weight / reference_weight).powi(TENSILE_EXPONENT);
+
+            // Mass-weighted so the pair's momentum is unchanged
+            let step = Self::kernel_gradient(kernel, diff)
+                * ((self.lambdas[i] + self.lambdas[j] + tensile) / rest_density);
+            self.corrections[i] = self.corrections[i] + step * particles[j].mass();
+            self.corrections[j] = self.corrections[j] - step * particles[i].mass();
+        }
+
+        for (particle, &correction) in particles.iter_mut().zip(&self.corrections) {
+            if Self::is_fluid(particle) {
+                particle.position = particle.position + correction;
+            }
+        }
+    }
+
+    /// Apply XSPH viscosity and vorticity confinement to the velocities implied by
+    /// position - position_old, writing the result back into position_old
+    pub fn correct_velocities(&mut self, particles: &mut [Particle], kernel: &SphParams, densities: &[f32], dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+        let count = particles.len();
+        Self::reset(&mut self.velocity_changes, count, Vec2::zero());
+        Self::reset(&mut self.vorticities, count, 0.0);
+        Self::reset(&mut self.vorticity_gradients, count, Vec2::zero());
+        self.velocities.clear();
+        self.velocities.extend(particles.iter().map(|p| (p.position - p.position_old) * (1.0 / dt)));
+
+        // Volume of each particle, m / ρ, used to weight neighbour sums
+        let volume = |index: usize| {
+            if densities[index] > 0.0 { particles[index].mass() / densities[index] } else { 0.0 }
+        };
+
+        for &(i, j) in &self.pairs {
+            let diff = particles[i].position - particles[j].position;
+            let weight = kernel.poly6(diff.x * diff.x + diff.y * diff.y);
+            let relative = self.velocities[j] - self.velocities[i];
+            self.velocity_changes[i] = self.velocity_changes[i] + relative * (self.params.xsph * volume(j) * weight);
+            self.velocity_changes[j] = self.velocity_changes[j] - relative * (self.params.xsph * volume(i) * weight);
+
+            // Scalar vorticity ω = Σ V_j (v_j - v_i) × ∇W, identical for both sides of the pair
+            let gradient = Self::kernel_gradient(kernel, diff);
+            let curl = relative.x * gradient.y - relative.y * gradient.x;
+            self.vorticities[i] += volume(j) * curl;
+            self.vorticities[j] += volume(i) * curl;
+        }
+
+        if self.params.vorticity > 0.0 {
+            for &(i, j) in &self.pairs {
+                let gradient = Self::kernel_gradient(kernel, particles[i].position - particles[j].position);
+                let difference = self.vorticities[j].abs() - self.vorticities[i].abs();
+                self.vorticity_gradients[i] = self.vorticity_gradients[i] + gradient * (volume(j) * difference);
+                self.vorticity_gradients[j] = self.vorticity_gradients[j] + gradient * (volume(i) * difference);
+            }
+
+            // Push along N × ω, where N points towards higher vorticity
+            for (index, change) in self.velocity_changes.iter_mut().enumerate() {
+                let gradient = self.vorticity_gradients[index];
+                let length = gradient.length();
+                if length > f32::EPSILON {
+                    let normal = gradient * (1.0 / length);
+                    let force = Vec2::new(normal.y, -normal.x) * (self.params.vorticity * self.vorticities[index]);
+                    *change = *change + force * dt;
+                }
+            }
+        }
+
+        for (index, particle) in particles.iter_mut().enumerate() {
+            if Self::is_fluid(particle) {
+                let velocity = self.velocities[index] + self.velocity_changes[index];
+                particle.position_old = particle.position - velocity * dt;
+            }
+        }
+    }
+
+    /// Gradient of the spiky kernel with respect to the first particle, `diff` = p_i - p_j
+    fn kernel_gradient(kernel: &SphParams, diff: Vec2) -> Vec2 {
+        let distance = diff.length();
+        if distance <= 0.0 {
+            return Vec2::zero();
+        }
+        diff * (-kernel.spiky_gradient(distance) / distance)
+    }
+
+    fn reset<T: Clone>(buffer: &mut Vec<T>, count: usize, value: T) {
+        buffer.clear();
+        buffer.resize(count, value);
+    }
+
+    /// Static and inactive particles are not part of the fluid
+    fn is_fluid(particle: &Particle) -> bool {
+        particle.active && particle.effective_inv_mass() > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lattice(spacing: f32, count: usize) -> Vec<Particle> {
+        (0..count * count)
+            .map(|i| {
+                let x = 100.0 + (i % count) as f32 * spacing;
+                let y = 100.0 + (i / count) as f32 * spacing;
+                Particle::new(Vec2::new(x, y), 4.0)
+            })
+            .collect()
+    }
+
+    fn centroid(particles: &[Particle]) -> Vec2 {
+        particles.iter().fold(Vec2::zero(), |sum, p| sum + p.position) * (1.0 / particles.len() as f32)
+    }
+
+    #[test]
+    fn test_density_constraints_remove_compression() {
+        let kernel = SphParams::default();
+        let mut pbf = Pbf::default();
+        let mut particles = lattice(5.0, 8);
+        let mut densities = vec![0.0; particles.len()];
+        let start = centroid(&particles);
+
+        pbf.find_neighbours(&particles, &kernel, 400.0, 400.0);
+        pbf.solve_density(&mut particles, &kernel, &mut densities);
+        let initial = densities.iter().fold(0.0f32, |max, &d| max.max(d));
+        for _ in 0..10 {
+            pbf.find_neighbours(&particles, &kernel, 400.0, 400.0);
+            pbf.solve_density(&mut particles, &kernel, &mut densities);
+        }
+        let relaxed = densities.iter().fold(0.0f32, |max, &d| max.max(d));
+
+        assert!(initial > kernel.rest_density * 1.5);
+        assert!(relaxed < kernel.rest_density * 1.1, "Density {} after relaxing", relaxed);
+        assert!((centroid(&particles) - start).length() < 1e-2);
+    }
+
+    #[test]
+    fn test_xsph_blends_neighbour_velocities() {
+        let kernel = SphParams::default();
+        let mut pbf = Pbf { params: PbfParams { vorticity: 0.0, ..PbfParams::default() }, ..Pbf::default() };
+        let mut particles = vec![Particle::new(Vec2::new(100.0, 100.0), 4.0), Particle::new(Vec2::new(108.0, 100.0), 4.0)];
+        particles[0].position_old = Vec2::new(100.0, 99.0);
+        particles[1].position_old = Vec2::new(108.0, 101.0);
+        let mut densities = vec![0.0; 2];
+
+        pbf.find_neighbours(&particles, &kernel, 400.0, 400.0);
+        pbf.solve_density(&mut particles, &kernel, &mut densities);
+        pbf.correct_velocities(&mut particles, &kernel, &densities, 1.0);
+
+        let v0 = particles[0].position - particles[0].position_old;
+        let v1 = particles[1].position - particles[1].position_old;
+        assert!(v0.y < 1.0 && v0.y > 0.0);
+        assert!(v1.y > -1.0 && v1.y < 0.0);
+        assert!((v0.y + v1.y).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_still_fluid_stays_still() {
+        let kernel = SphParams::default();
+        let mut pbf = Pbf::default();
+        let mut particles = lattice(8.0, 6);
+        let mut densities = vec![0.0; particles.len()];
+
+        pbf.find_neighbours(&particles, &kernel, 400.0, 400.0);
+        pbf.solve_density(&mut particles, &kernel, &mut densities);
+        for particle in &mut particles {
+            particle.position_old = particle.position;
+        }
+        pbf.correct_velocities(&mut particles, &kernel, &densities, 1.0 / 60.0);
+
+        assert!(particles.iter().all(|p| p.position == p.position_old));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{Particle, Vec2};
+
+/// A group of particles pulled towards the best rigid fit of its rest shape (Müller et al. 2005)
+#[derive(Clone, Debug)]
+pub struct ShapeCluster {
+    indices: Vec<usize>,
+    // Rest position of each member, in the cluster's own frame
+    rest_positions: Vec<Vec2>,
+    // 1 snaps members onto the fitted shape every pass, smaller values let it deform
+    stiffness: f32,
+}
+
+impl ShapeCluster {
+    /// Build a cluster whose rest shape is the members' current arrangement
+    pub fn new(particles: &[Particle], indices: Vec<usize>, stiffness: f32) -> Result<ShapeCluster, String> {
+        if indices.len() < 2 {
+            return Err(format!("A shape cluster needs at least 2 particles, got {}", indices.len()));
+        }
+        let mut sorted = indices.clone();
+        sorted.sort_unstable();
+        if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err("A shape cluster cannot contain the same particle twice".to_string());
+        }
+
+        let rest_positions = indices.iter().map(|&i| particles[i].position).collect();
+        Ok(ShapeCluster { indices, rest_positions, stiffness: Self::validate_stiffness(stiffness)? })
+    }
+
+    /// Check that a stiffness is within [0, 1]
+    pub fn validate_stiffness(stiffness: f32) -> Result<f32, String> {
+        crate::config::validate_range("stiffness", stiffness, 0.0, 1.0)
+    }
+
+    /// Fraction of the way members move to their goals, over all passes of a step
+    pub fn stiffness(&self) -> f32 {
+        self.stiffness
+    }
+
+    /// Change the stiffness, validated like `new`
+    pub fn set_stiffness(&mut self, stiffness: f32) -> Result<(), String> {
+        self.stiffness = Self::validate_stiffness(stiffness)?;
+        Ok(())
+    }
+
+    /// Particle slots in the cluster
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Drop members for which `keep` returns false; returns false once fewer than 2 remain
+    fn retain_members(&mut self, keep: impl Fn(usize) -> bool) -> bool {
+        let mut index = 0;
+        while index < self.indices.len() {
+            if keep(self.indices[index]) {
+                index += 1;
+            } else {
+                self.indices.swap_remove(index);
+                self.rest_positions.swap_remove(index);
+            }
+        }
+        self.indices.len() >= 2
+    }
+
+    /// Move every dynamic member part of the way to its goal position. `pass_stiffness`
+    /// is the fraction applied this pass. Static members are neither fitted nor moved.
+    fn solve(&self, particles: &mut [Particle], pass_stiffness: f32) {
+        // Mass-weighted centres of the rest shape and of the current positions
+        let mut total = 0.0;
+        let (mut rest_centre, mut centre) = (Vec2::zero(), Vec2::zero());
+        for (&index, &rest) in self.indices.iter().zip(&self.rest_positions) {
+            let w = Self::weight(&particles[index]);
+            total += w;
+            rest_centre = rest_centre + rest * w;
+            centre = centre + particles[index].position * w;
+        }
+        if total <= 0.0 {
+            return;
+        }
+        rest_centre = rest_centre * (1.0 / total);
+        centre = centre * (1.0 / total);
+
+        // In 2D the optimal rotation of the rest shape onto the current one has a closed form
+        let (mut dot, mut cross) = (0.0, 0.0);
+        for (&index, &rest) in self.indices.iter().zip(&self.rest_positions) {
+            let w = Self::weight(&particles[index]);
+            let (q, p) = (rest - rest_centre, particles[index].position - centre);
+            dot += w * (q.x * p.x + q.y * p.y);
+            cross += w * (q.x * p.y - q.y * p.x);
+        }
+        let angle = cross.atan2(dot);
+        let (sin, cos) = angle.sin_cos();
+
+        for (&index, &rest) in self.indices.iter().zip(&self.rest_positions) {
+            if Self::weight(&particles[index]) <= 0.0 {
+                continue;
+            }
+            let q = rest - rest_centre;
+            let goal = centre + Vec2::new(q.x * cos - q.y * sin, q.x * sin + q.y * cos);
+            let particle = &mut particles[index];
+            particle.position = particle.position + (goal - particle.position) * pass_stiffness;
+        }
+    }
+
+    /// Mass used in the fit; static, pinned, kinematic and inactive members do not count
+    fn weight(particle: &Particle) -> f32 {
+        let inv_mass = particle.effective_inv_mass();
+        if particle.active && inv_mass > 0.0 { 1.0 / inv_mass } else { 0.0 }
+    }
+}
+
+/// Shape-matching clusters addressed by ID, solved on every relaxation pass
+#[derive(Clone, Debug, Default)]
+pub struct ShapeClusters {
+    clusters: Vec<(u32, ShapeCluster)>,
+    next_id: u32,
+}
+
+impl ShapeClusters {
+    /// Store a cluster and return its ID
+    pub fn add(&mut self, cluster: ShapeCluster) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.clusters.push((id, cluster));
+        id
+    }
+
+    /// Remove a cluster; returns false if the ID is unknown
+    pub fn remove(&mut self, id: u32) -> bool {
+        let count = self.clusters.len();
+        self.clusters.retain(|(cluster_id, _)| *cluster_id != id);
+        self.clusters.len() != count
+    }
+
+    /// Look up a cluster by ID
+    pub fn get(&self, id: u32) -> Option<&ShapeCluster> {
+        self.clusters.iter().find(|(cluster_id, _)| *cluster_id == id).map(|(_, c)| c)
+    }
+
+    /// Look up a cluster by ID for changes
+    pub fn get_mut(&mut self, id: u32) -> Result<&mut ShapeCluster, String> {
+        self.clusters
+            .iter_mut()
+            .find(|(cluster_id, _)| *cluster_id == id)
+            .map(|(_, c)| c)
+            .ok_or_else(|| format!("Unknown shape cluster {}", id))
+    }
+
+    /// Take a particle slot out of every cluster, dropping clusters left with fewer than 2 members
+    pub fn remove_particle(&mut self, index: usize) {
+        self.clusters.retain_mut(|(_, cluster)| cluster.retain_members(|i| i != index));
+    }
+
+    /// Take inactive slots out of every cluster, dropping clusters left with fewer than 2 members
+    pub fn remove_inactive(&mut self, particles: &[Particle]) {
+        self.clusters
+            .retain_mut(|(_, cluster)| cluster.retain_members(|i| particles.get(i).is_some_and(|p| p.active)));
+    }
+
+    /// Remove every cluster
+    pub fn clear(&mut self) {
+        self.clusters.clear();
+    }
+
+    /// Number of stored clusters
+    pub fn len(&self) -> usize {
+        self.clusters.len()
+    }
+
+    /// One pass over all clusters. Each cluster's stiffness is spread over `passes` so
+    /// its overall effect does not depend on the iteration count.
+    pub fn solve(&self, particles: &mut [Particle], passes: u32) {
+        for (_, cluster) in &self.clusters {
+            let pass_stiffness = 1.0 - (1.0 - cluster.stiffness).powf(1.0 / passes.max(1) as f32);
+            cluster.solve(particles, pass_stiffness);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Vec<Particle> {
+        [(0.0, 0.0), (20.0, 0.0), (0.0, 10.0)]
+            .iter()
+            .map(|&(x, y)| Particle::new(Vec2::new(x, y), 1.0))
+            .collect()
+    }
+
+    fn distance(particles: &[Particle], a: usize, b: usize) -> f32 {
+        (particles[a].position - particles[b].position).length()
+    }
+
+    #[test]
+    fn test_rigid_cluster_restores_shape_but_keeps_rotation() {
+        let mut particles = triangle();
+        let mut clusters = ShapeClusters::default();
+        clusters.add(ShapeCluster::new(&particles, vec![0, 1, 2], 1.0).unwrap());
+
+        // Rotate the triangle a quarter turn about the origin, then squash one corner
+        for particle in &mut particles {
+            particle.position = Vec2::new(-particle.position.y, particle.position.x) + Vec2::new(50.0, 50.0);
+        }
+        particles[1].position.y -= 5.0;
+        clusters.solve(&mut particles, 1);
+
+        assert!((distance(&particles, 0, 1) - 20.0).abs() < 1e-3);
+        assert!((distance(&particles, 0, 2) - 10.0).abs() < 1e-3);
+        // Still pointing the way it was turned
+        let edge = particles[1].position - particles[0].position;
+        assert!(edge.y > 15.0 && edge.x.abs() < 3.0, "Edge {:?}", edge);
+    }
+
+    #[test]
+    fn test_soft_cluster_moves_part_way() {
+        let mut particles = triangle();
+        let mut clusters = ShapeClusters::default();
+        let id = clusters.add(ShapeCluster::new(&particles, vec![0, 1, 2], 0.5).unwrap());
+        particles[1].position.x = 30.0;
+        clusters.solve(&mut particles, 1);
+
+        let stretched = distance(&particles, 0, 1);
+        assert!(stretched > 20.0 && stretched < 30.0, "Length {}", stretched);
+        assert!(clusters.get_mut(id).unwrap().set_stiffness(1.5).is_err());
+        assert!(ShapeCluster::new(&particles, vec![0, 0], 1.0).is_err());
+    }
+
+    #[test]
+    fn test_members_leave_with_their_particles() {
+        let particles = triangle();
+        let mut clusters = ShapeClusters::default();
+        let id = clusters.add(ShapeCluster::new(&particles, vec![0, 1, 2], 1.0).unwrap());
+
+        clusters.remove_particle(1);
+        assert_eq!(clusters.get(id).unwrap().indices().len(), 2);
+        clusters.remove_particle(0);
+        assert_eq!(clusters.len(), 0);
+    }
+}
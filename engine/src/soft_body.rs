@@ -0,0 +1,198 @@
+use crate::{Particle, Vec2};
+
+/// Bend links skip one particle and are this much softer than the structural links,
+/// so cloth folds instead of behaving like a sheet of card. Infinite (rigid) stiffness
+/// stays infinite, so rigid cloth keeps rigid bend links and does not fold.
+pub const BEND_STIFFNESS_FACTOR: f32 = 0.1;
+
+/// How a cloth link is oriented in the lattice
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClothLink {
+    Structural,
+    Shear,
+    Bend,
+}
+
+/// Index pairs of the links in a row-major `columns` × `rows` cloth lattice
+pub fn cloth_links(columns: usize, rows: usize) -> Vec<(usize, usize, ClothLink)> {
+    let index = |column: usize, row: usize| row * columns + column;
+    let mut links = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let here = index(column, row);
+            if column + 1 < columns {
+                links.push((here, index(column + 1, row), ClothLink::Structural));
+            }
+            if row + 1 < rows {
+                links.push((here, index(column, row + 1), ClothLink::Structural));
+            }
+            if column + 1 < columns && row + 1 < rows {
+                links.push((here, index(column + 1, row + 1), ClothLink::Shear));
+                links.push((index(column + 1, row), index(column, row + 1), ClothLink::Shear));
+            }
+            if column + 2 < columns {
+                links.push((here, index(column + 2, row), ClothLink::Bend));
+            }
+            if row + 2 < rows {
+                links.push((here, index(column, row + 2), ClothLink::Bend));
+            }
+        }
+    }
+    links
+}
+
+/// Signed area enclosed by a closed loop of particles (shoelace formula)
+fn loop_area(particles: &[Particle], indices: &[usize]) -> f32 {
+    let mut twice_area = 0.0;
+    for (k, &i) in indices.iter().enumerate() {
+        let (a, b) = (particles[i].position, particles[indices[(k + 1) % indices.len()]].position);
+        twice_area += a.x * b.y - b.x * a.y;
+    }
+    twice_area * 0.5
+}
+
+/// Keeps the area enclosed by a loop of particles at a target, like gas pressure in a balloon
+#[derive(Clone, Debug)]
+pub struct AreaConstraint {
+    indices: Vec<usize>,
+    target_area: f32,
+    gradients: Vec<Vec2>,
+}
+
+impl AreaConstraint {
+    /// Hold the loop at `pressure` times its current area (1 keeps it as it is, more inflates it)
+    pub fn new(particles: &[Particle], indices: Vec<usize>, pressure: f32) -> Result<AreaConstraint, String> {
+        if indices.len() < 3 {
+            return Err(format!("An area constraint needs at least 3 particles, got {}", indices.len()));
+        }
+        let target_area = loop_area(particles, &indices) * Self::validate_pressure(pressure)?;
+        if target_area.abs() <= f32::EPSILON {
+            return Err("Area constraint particles must not be collinear".to_string());
+        }
+
+        Ok(AreaConstraint { indices, target_area, gradients: Vec::new() })
+    }
+
+    /// Check that a pressure is finite and positive
+    pub fn validate_pressure(pressure: f32) -> Result<f32, String> {
+        if !(pressure.is_finite() && pressure > 0.0) {
+            return Err(format!("Pressure must be finite and positive, got {}", pressure));
+        }
+        Ok(pressure)
+    }
+
+    /// Move the loop's particles along the area gradient until the area matches the target
+    fn solve(&mut self, particles: &mut [Particle]) {
+        let count = self.indices.len();
+        self.gradients.clear();
+        let mut denominator = 0.0;
+        for k in 0..count {
+            let previous = particles[self.indices[(k + count - 1) % count]].position;
+            let next = particles[self.indices[(k + 1) % count]].position;
+            let gradient = Vec2::new(next.y - previous.y, previous.x - next.x) * 0.5;
+            let particle = &particles[self.indices[k]];
+            denominator += particle.effective_inv_mass() * (gradient.x * gradient.x + gradient.y * gradient.y);
+            self.gradients.push(gradient);
+        }
+        if denominator <= f32::EPSILON {
+            return;
+        }
+
+        let scale = -(loop_area(particles, &self.indices) - self.target_area) / denominator;
+        for (&index, &gradient) in self.indices.iter().zip(&self.gradients) {
+            let particle = &mut particles[index];
+            particle.position = particle.position + gradient * (scale * particle.effective_inv_mass());
+        }
+    }
+}
+
+/// Area constraints of every soft body, solved on every relaxation pass
+#[derive(Clone, Debug, Default)]
+pub struct AreaConstraints {
+    constraints: Vec<AreaConstraint>,
+}
+
+impl AreaConstraints {
+    /// Store an area constraint
+    pub fn add(&mut self, constraint: AreaConstraint) {
+        self.constraints.push(constraint);
+    }
+
+    /// Remove every area constraint that includes a particle slot
+    pub fn remove_particle(&mut self, index: usize) {
+        self.constraints.retain(|c| !c.indices.contains(&index));
+    }
+
+    /// Remove area constraints that include inactive slots
+    pub fn remove_inactive(&mut self, particles: &[Particle]) {
+        self.constraints.retain(|c| c.indices.iter().all(|&i| particles.get(i).is_some_and(|p| p.active)));
+    }
+
+    /// Remove every area constraint
+    pub fn clear(&mut self) {
+        self.constraints.clear();
+    }
+
+    /// Number of stored area constraints
+    pub fn len(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// One Gauss-Seidel pass over all area constraints
+    pub fn solve(&mut self, particles: &mut [Particle]) {
+        for constraint in &mut self.constraints {
+            constraint.solve(particles);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: f32) -> Vec<Particle> {
+        [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)]
+            .iter()
+            .map(|&(x, y)| Particle::new(Vec2::new(x, y), 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_cloth_link_counts() {
+        let links = cloth_links(3, 2);
+        let count = |kind: ClothLink| links.iter().filter(|(_, _, k)| *k == kind).count();
+        assert_eq!(count(ClothLink::Structural), 7);
+        assert_eq!(count(ClothLink::Shear), 4);
+        assert_eq!(count(ClothLink::Bend), 2);
+    }
+
+    #[test]
+    fn test_area_constraint_restores_squashed_loop() {
+        let mut particles = square(10.0);
+        let mut constraint = AreaConstraint::new(&particles, vec![0, 1, 2, 3], 1.0).unwrap();
+
+        // Squash the square to half its height
+        particles[2].position.y = 5.0;
+        particles[3].position.y = 5.0;
+        for _ in 0..20 {
+            constraint.solve(&mut particles);
+        }
+
+        assert!((loop_area(&particles, &[0, 1, 2, 3]) - 100.0).abs() < 1e-2);
+        assert!(AreaConstraint::new(&particles, vec![0, 1], 1.0).is_err());
+        assert!(AreaConstraint::new(&particles, vec![0, 1, 2], 0.0).is_err());
+    }
+
+    #[test]
+    fn test_pressure_inflates_loop() {
+        let mut particles = square(10.0);
+        let mut constraint = AreaConstraint::new(&particles, vec![0, 1, 2, 3], 2.0).unwrap();
+        particles[0].inv_mass = 0.0;
+        for _ in 0..20 {
+            constraint.solve(&mut particles);
+        }
+
+        assert!((loop_area(&particles, &[0, 1, 2, 3]) - 200.0).abs() < 1e-1);
+        assert_eq!(particles[0].position, Vec2::zero());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{Particle, Vec2};
+
+/// Marker for particles that are not stored in any cell (inactive or not yet inserted)
+const NO_CELL: usize = usize::MAX;
+
+/// Upper bound on grid columns/rows so tiny radii cannot blow up memory use
+const MAX_GRID_DIMENSION: usize = 1024;
+
+/// Uniform spatial hash grid covering the container, used as a collision broadphase.
+///
+/// Each particle remembers the cell it was last stored in, so `update` only moves
+/// particles that crossed a cell boundary since the previous call. The grid is
+/// rebuilt from scratch only when its cell size or dimensions change.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    // Particle indices stored per cell, row-major
+    cells: Vec<Vec<usize>>,
+    // Cell index each particle currently lives in, or NO_CELL
+    particle_cells: Vec<usize>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpatialGrid {
+    /// Create an empty grid; cells are allocated on the first `update`
+    pub fn new() -> Self {
+        SpatialGrid {
+            cell_size: 0.0,
+            cols: 0,
+            rows: 0,
+            cells: Vec::new(),
+            particle_cells: Vec::new(),
+        }
+    }
+
+    /// Bring the grid in sync with the current particle positions.
+    /// Positions outside the container are clamped into the border cells.
+    pub fn update(&mut self, particles: &[Particle], cell_size: f32, width: f32, height: f32) {
+        let width = width.max(1.0);
+        let height = height.max(1.0);
+        let max_extent = width.max(height);
+        let cell_size = cell_size
+            .max(max_extent / MAX_GRID_DIMENSION as f32)
+            .max(f32::EPSILON);
+        let cols = ((width / cell_size).ceil() as usize).max(1);
+        let rows = ((height / cell_size).ceil() as usize).max(1);
+
+        if cell_size != self.cell_size || cols != self.cols || rows != self.rows {
+            // Layout changed: every stored cell index is stale, start over
+            self.cell_size = cell_size;
+            self.cols = cols;
+            self.rows = rows;
+            self.cells.iter_mut().for_each(Vec::clear);
+            self.cells.resize_with(cols * rows, Vec::new);
+            self.particle_cells.clear();
+        }
+
+        // Drop particles that no longer exist
+        while self.particle_cells.len() > particles.len() {
+            let index = self.particle_cells.len() - 1;
+            let cell = self.particle_cells[index];
+            if cell != NO_CELL {
+                Self::remove_from_cell(&mut self.cells[cell], index);
+            }
+            self.particle_cells.pop();
+        }
+        self.particle_cells.resize(particles.len(), NO_CELL);
+
+        for (index, particle) in particles.iter().enumerate() {
+            let cell = if particle.active {
+                let (cx, cy) = self.cell_coords(particle.position);
+                cy * self.cols + cx
+            } else {
+                NO_CELL
+            };
+
+            let old_cell = self.particle_cells[index];
+            if cell == old_cell {
+                continue;
+            }
+
+            if old_cell != NO_CELL {
+                Self::remove_from_cell(&mut self.cells[old_cell], index);
+            }
+            if cell != NO_CELL {
+                self.cells[cell].push(index);
+            }
+            self.particle_cells[index] = cell;
+        }
+    }
+
+    /// Visit every candidate pair exactly once: particles sharing a cell or lying in
+    /// adjacent cells. Pair order is unspecified.
+    pub fn for_each_candidate_pair<F: FnMut(usize, usize)>(&self, mut f: F) {
+        // Only look "forward" so each neighbouring cell pair is visited once
+        const FORWARD_NEIGHBOURS: [(isize, isize); 4] = [(1, 0), (-1, 1), (0, 1), (1, 1)];
+
+        for cy in 0..self.rows {
+            for cx in 0..self.cols {
+                let cell = &self.cells[cy * self.cols + cx];
+                if cell.is_empty() {
+                    continue;
+                }
+
+                for (k, &a) in cell.iter().enumerate() {
+                    for &b in &cell[k + 1..] {
+                        f(a, b);
+                    }
+                }
+
+                for (dx, dy) in FORWARD_NEIGHBOURS {
+                    let nx = cx as isize + dx;
+                    let ny = cy as isize + dy;
+                    if nx < 0 || nx >= self.cols as isize || ny >= self.rows as isize {
+                        continue;
+                    }
+
+                    let neighbour = &self.cells[ny as usize * self.cols + nx as usize];
+                    for &a in cell {
+                        for &b in neighbour {
+                            f(a, b);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Cell coordinates for a position, clamped into the grid
+    fn cell_coords(&self, position: Vec2) -> (usize, usize) {
+        let cx = (position.x / self.cell_size).floor().max(0.0) as usize;
+        let cy = (position.y / self.cell_size).floor().max(0.0) as usize;
+        (cx.min(self.cols - 1), cy.min(self.rows - 1))
+    }
+
+    fn remove_from_cell(cell: &mut Vec<usize>, index: usize) {
+        if let Some(slot) = cell.iter().position(|&entry| entry == index) {
+            cell.swap_remove(slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_pairs(grid: &SpatialGrid) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        grid.for_each_candidate_pair(|a, b| pairs.push((a.min(b), a.max(b))));
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn test_candidate_pairs_include_neighbouring_cells() {
+        let particles = vec![
+            Particle::new(Vec2::new(10.0, 10.0), 4.0),
+            Particle::new(Vec2::new(12.0, 10.0), 4.0), // Same cell as 0
+            Particle::new(Vec2::new(18.0, 10.0), 4.0), // Right neighbour of 0
+            Particle::new(Vec2::new(90.0, 90.0), 4.0), // Far away
+        ];
+        let mut grid = SpatialGrid::new();
+        grid.update(&particles, 8.0, 100.0, 100.0);
+
+        let pairs = collect_pairs(&grid);
+        assert!(pairs.contains(&(0, 1)));
+        assert!(pairs.contains(&(0, 2)));
+        assert!(pairs.contains(&(1, 2)));
+        assert!(!pairs.iter().any(|&(a, b)| a == 3 || b == 3));
+
+        // Every pair must be reported only once
+        let mut deduped = pairs.clone();
+        deduped.dedup();
+        assert_eq!(deduped, pairs);
+    }
+
+    #[test]
+    fn test_incremental_update_moves_particles() {
+        let mut particles = vec![
+            Particle::new(Vec2::new(5.0, 5.0), 4.0),
+            Particle::new(Vec2::new(60.0, 60.0), 4.0),
+        ];
+        let mut grid = SpatialGrid::new();
+        grid.update(&particles, 8.0, 100.0, 100.0);
+        assert!(collect_pairs(&grid).is_empty());
+
+        // Move particle 0 next to particle 1
+        particles[0].position = Vec2::new(58.0, 60.0);
+        grid.update(&particles, 8.0, 100.0, 100.0);
+        assert_eq!(collect_pairs(&grid), vec![(0, 1)]);
+
+        // Inactive particles leave the grid
+        particles[1].active = false;
+        grid.update(&particles, 8.0, 100.0, 100.0);
+        assert!(collect_pairs(&grid).is_empty());
+    }
+
+    #[test]
+    fn test_out_of_bounds_positions_are_clamped() {
+        let particles = vec![
+            Particle::new(Vec2::new(-50.0, -50.0), 4.0),
+            Particle::new(Vec2::new(2.0, 2.0), 4.0),
+        ];
+        let mut grid = SpatialGrid::new();
+        grid.update(&particles, 8.0, 100.0, 100.0);
+
+        assert_eq!(collect_pairs(&grid), vec![(0, 1)]);
+    }
+}
@@ -0,0 +1,217 @@
+use std::f32::consts::PI;
+
+use crate::spatial_grid::SpatialGrid;
+use crate::{Particle, Vec2};
+
+/// Exponent of the Tait equation of state (the usual choice for water)
+const TAIT_GAMMA: i32 = 7;
+
+/// Tunable parameters of the SPH fluid model
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphParams {
+    // Target density in mass per unit area; the default suits unit-mass particles 8 units apart
+    pub rest_density: f32,
+    // Kernel support h: particles further apart than this do not interact
+    pub smoothing_radius: f32,
+    // Tait pressure constant B in p = B((ρ/ρ₀)^γ - 1)
+    pub stiffness: f32,
+    // Viscosity coefficient, roughly the rate (per second) at which neighbours share velocity
+    pub viscosity: f32,
+}
+
+impl Default for SphParams {
+    fn default() -> Self {
+        SphParams {
+            rest_density: 0.015,
+            smoothing_radius: 20.0,
+            stiffness: 200.0,
+            viscosity: 10.0,
+        }
+    }
+}
+
+impl SphParams {
+    /// Poly6 kernel W(r) for a squared distance, used for density
+    pub fn poly6(&self, distance_sq: f32) -> f32 {
+        let h_sq = self.smoothing_radius * self.smoothing_radius;
+        if distance_sq >= h_sq {
+            return 0.0;
+        }
+        let diff = h_sq - distance_sq;
+        4.0 / (PI * h_sq.powi(4)) * diff * diff * diff
+    }
+
+    /// Magnitude of the spiky kernel gradient |∇W(r)|, used for pressure
+    pub fn spiky_gradient(&self, distance: f32) -> f32 {
+        let h = self.smoothing_radius;
+        if distance >= h {
+            return 0.0;
+        }
+        30.0 / (PI * h.powi(5)) * (h - distance) * (h - distance)
+    }
+
+    /// Viscosity kernel laplacian ∇²W(r)
+    fn viscosity_laplacian(&self, distance: f32) -> f32 {
+        let h = self.smoothing_radius;
+        if distance >= h {
+            return 0.0;
+        }
+        40.0 / (PI * h.powi(5)) * (h - distance)
+    }
+
+    /// Tait equation of state; negative pressures are dropped so the fluid does not clump
+    pub fn pressure(&self, density: f32) -> f32 {
+        (self.stiffness * ((density / self.rest_density).powi(TAIT_GAMMA) - 1.0)).max(0.0)
+    }
+}
+
+/// Smoothed Particle Hydrodynamics state: parameters, neighbour grid and per-particle fields
+#[derive(Default)]
+pub struct Sph {
+    pub params: SphParams,
+    grid: SpatialGrid,
+    // Per-slot density and pressure from the last `compute`, 0 for inactive slots
+    pub densities: Vec<f32>,
+    pub pressures: Vec<f32>,
+}
+
+impl Sph {
+    /// Keep the density and pressure buffers one entry per particle slot
+    pub fn resize(&mut self, count: usize) {
+        self.densities.resize(count, 0.0);
+        self.pressures.resize(count, 0.0);
+    }
+
+    /// Compute densities and pressures, then add pressure and viscosity accelerations to
+    /// `accelerations`. `velocities` holds the current velocity of every particle slot.
+    pub fn compute(
+        &mut self,
+        particles: &[Particle],
+        velocities: &[Vec2],
+        width: f32,
+        height: f32,
+        accelerations: &mut [Vec2],
+    ) {
+        let params = self.params;
+        self.resize(particles.len());
+        self.grid.update(particles, params.smoothing_radius, width, height);
+
+        // Density: every particle counts itself, then each neighbour pair once
+        for (index, particle) in particles.iter().enumerate() {
+            self.densities[index] = if Self::is_fluid(particle) { particle.mass() * params.poly6(0.0) } else { 0.0 };
+        }
+        let densities = &mut self.densities;
+        self.grid.for_each_candidate_pair(|i, j| {
+            let (a, b) = (&particles[i], &particles[j]);
+            if !(Self::is_fluid(a) && Self::is_fluid(b)) {
+                return;
+            }
+            let diff = a.position - b.position;
+            let weight = params.poly6(diff.x * diff.x + diff.y * diff.y);
+            densities[i] += b.mass() * weight;
+            densities[j] += a.mass() * weight;
+        });
+
+        for (pressure, &density) in self.pressures.iter_mut().zip(&self.densities) {
+            *pressure = if density > 0.0 { params.pressure(density) } else { 0.0 };
+        }
+
+        // Symmetric pressure and viscosity forces, so momentum is conserved
+        let (densities, pressures) = (&self.densities, &self.pressures);
+        self.grid.for_each_candidate_pair(|i, j| {
+            let (a, b) = (&particles[i], &particles[j]);
+            if !(Self::is_fluid(a) && Self::is_fluid(b)) {
+                return;
+            }
+            let diff = a.position - b.position;
+            let distance = diff.length();
+            if distance >= params.smoothing_radius || distance <= 0.0 {
+                return;
+            }
+
+            let direction = diff * (1.0 / distance);
+            let pressure_term = pressures[i] / (densities[i] * densities[i]) + pressures[j] / (densities[j] * densities[j]);
+            let pressure_force = direction * (pressure_term * params.spiky_gradient(distance));
+
+            let viscosity_weight = params.viscosity * params.viscosity_laplacian(distance) / (densities[i] * densities[j]);
+            let viscosity_force = (velocities[j] - velocities[i]) * viscosity_weight;
+
+            // Force per unit mass product, scaled by the partner's mass for each side
+            let pair_force = pressure_force + viscosity_force;
+            accelerations[i] = accelerations[i] + pair_force * b.mass();
+            accelerations[j] = accelerations[j] - pair_force * a.mass();
+        });
+    }
+
+    /// Static and inactive particles are not part of the fluid
+    fn is_fluid(particle: &Particle) -> bool {
+        particle.active && particle.effective_inv_mass() > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lattice(spacing: f32, count: usize) -> Vec<Particle> {
+        (0..count * count)
+            .map(|i| {
+                let x = 100.0 + (i % count) as f32 * spacing;
+                let y = 100.0 + (i / count) as f32 * spacing;
+                Particle::new(Vec2::new(x, y), 4.0)
+            })
+            .collect()
+    }
+
+    fn compute(sph: &mut Sph, particles: &[Particle]) -> Vec<Vec2> {
+        let mut accelerations = vec![Vec2::zero(); particles.len()];
+        let velocities = vec![Vec2::zero(); particles.len()];
+        sph.compute(particles, &velocities, 400.0, 400.0, &mut accelerations);
+        accelerations
+    }
+
+    #[test]
+    fn test_interior_density_matches_rest_density() {
+        let mut sph = Sph::default();
+        let particles = lattice(8.0, 11);
+        compute(&mut sph, &particles);
+
+        // Centre of an 8-unit lattice sits just above the default rest density
+        let centre = sph.densities[5 * 11 + 5];
+        assert!((centre - sph.params.rest_density).abs() < sph.params.rest_density * 0.1, "Density {}", centre);
+
+        // Edge particles are missing neighbours
+        assert!(sph.densities[0] < centre);
+        assert_eq!(sph.pressures[0], 0.0);
+    }
+
+    #[test]
+    fn test_compressed_fluid_pushes_outward() {
+        let mut sph = Sph::default();
+        let particles = lattice(5.0, 5);
+        let accelerations = compute(&mut sph, &particles);
+
+        assert!(sph.pressures[12] > 0.0);
+        // Left column is pushed left, right column right, centre stays balanced
+        assert!(accelerations[10].x < 0.0);
+        assert!(accelerations[14].x > 0.0);
+        let scale = accelerations[10].length();
+        assert!(accelerations[12].length() < scale * 1e-4);
+
+        // Momentum is conserved
+        let total = accelerations.iter().fold(Vec2::zero(), |sum, &a| sum + a);
+        assert!(total.length() < scale * 1e-4);
+    }
+
+    #[test]
+    fn test_viscosity_damps_relative_motion() {
+        let mut sph = Sph::default();
+        let particles = vec![Particle::new(Vec2::new(100.0, 100.0), 4.0), Particle::new(Vec2::new(110.0, 100.0), 4.0)];
+        let velocities = vec![Vec2::new(0.0, 10.0), Vec2::new(0.0, -10.0)];
+        let mut accelerations = vec![Vec2::zero(); 2];
+        sph.compute(&particles, &velocities, 400.0, 400.0, &mut accelerations);
+
+        assert!(accelerations[0].y < 0.0);
+        assert!(accelerations[1].y > 0.0);
+    }
+}